@@ -2,7 +2,7 @@ use std::io;
 use std::net::{SocketAddr, UdpSocket};
 
 use crate::proto::Command;
-use crate::synth::{Environment, GenBox, Parameters, SampleBuffer};
+use crate::synth::{Environment, GenBox, Limiter, Parameters, SampleBuffer};
 
 pub struct Voice {
     pub gen: GenBox,
@@ -15,6 +15,9 @@ pub struct Client {
     pub env: Environment,
     pub frames: usize,
     pub buf: SampleBuffer,
+    // If set, voices are summed at full level and limited; otherwise the sum is divided by the
+    // number of voices.
+    pub limiter: Option<Limiter>,
     norm: SampleBuffer,
 }
 
@@ -41,6 +44,7 @@ impl Client {
             env: env,
             frames: 0,
             buf: buf,
+            limiter: None,
             norm: SampleBuffer::new(1),
         })
     }
@@ -190,8 +194,13 @@ impl Client {
             self.buf.sum_into(voice.gen.eval(&voice.params));
         }
 
-        self.norm.set(1.0 / (len as f32));
-        self.buf.mul_into(&self.norm);
+        match self.limiter {
+            Some(ref mut limiter) => limiter.process_buffer(&mut self.buf),
+            None => {
+                self.norm.set(1.0 / (len as f32));
+                self.buf.mul_into(&self.norm);
+            }
+        }
         self.frames += self.buf.len();
     }
 
@@ -79,6 +79,8 @@ pub struct Parser<T: Iterator<Item = char>> {
     env: Environment,
     token: Token,
     pushback: Option<Token>,
+    replay: Vec<Token>,
+    recording: Vec<Vec<Token>>,
     factories: HashMap<String, &'static dyn GeneratorFactory>,
 }
 
@@ -90,6 +92,8 @@ impl<T: Iterator<Item = char>> Parser<T> {
             env: env,
             token: token,
             pushback: None,
+            replay: Vec::new(),
+            recording: Vec::new(),
             factories: all_factories(),
         })
     }
@@ -116,7 +120,17 @@ impl<T: Iterator<Item = char>> Parser<T> {
         } else {
             Ok(match self.pushback {
                 Some(_) => mem::replace(&mut self.pushback, None).unwrap(),
-                None => mem::replace(&mut self.token, self.tzr.next_token()?),
+                None => {
+                    let next = match self.replay.pop() {
+                        Some(tok) => tok,
+                        None => self.tzr.next_token()?,
+                    };
+                    let tok = mem::replace(&mut self.token, next);
+                    for rec in self.recording.iter_mut() {
+                        rec.push(tok.clone());
+                    }
+                    tok
+                }
             })
         }
     }
@@ -158,6 +172,8 @@ impl<T: Iterator<Item = char>> Parser<T> {
                 break;
             }
 
+            // Feedback names are local to each generator in the vector.
+            self.env.feedback = Default::default();
             ret.push(self.parse_gen_rel()?);
 
             if self.expect_op(',').is_err() {
@@ -288,13 +304,13 @@ impl<T: Iterator<Item = char>> Parser<T> {
 
     pub fn parse_gen_factors(&mut self) -> Result<GenBox, Box<dyn Error>> {
         let mut gens: Vec<GenBox> = Vec::new();
-        gens.push(self.parse_gen()?);
+        gens.push(self.parse_gen_power()?);
 
         loop {
             match *self.cur_token() {
                 Token::Oper('*') => {
                     self.expect_op('*')?;
-                    gens.push(self.parse_gen()?);
+                    gens.push(self.parse_gen_power()?);
                 }
                 Token::Oper('/') => {
                     self.expect_op('/')?;
@@ -302,9 +318,10 @@ impl<T: Iterator<Item = char>> Parser<T> {
                         env: self.env.clone(),
                         ..Default::default()
                     };
-                    params
-                        .vars
-                        .insert("0".to_string(), ParamValue::Generator(self.parse_gen()?));
+                    params.vars.insert(
+                        "0".to_string(),
+                        ParamValue::Generator(self.parse_gen_power()?),
+                    );
                     let factory = self.factories.get("reciprocate").ok_or(ErrorType::new(
                         ErrorKind::UnknownGen("reciprocate".to_string()),
                     ))?;
@@ -314,10 +331,34 @@ impl<T: Iterator<Item = char>> Parser<T> {
                             .map_err(GenFactoryErrorType::from)?,
                     );
                 }
+                Token::Oper('%') => {
+                    // Left-associative at the same precedence as *, so fold what we have first.
+                    self.expect_op('%')?;
+                    let left = self.build_gen_product(mem::take(&mut gens))?;
+                    let right = self.parse_gen_power()?;
+                    gens.push(self.build_gen_binary("mod", left, right)?);
+                }
                 _ => break,
             }
         }
 
+        self.build_gen_product(gens)
+    }
+
+    pub fn parse_gen_power(&mut self) -> Result<GenBox, Box<dyn Error>> {
+        let base = self.parse_gen()?;
+
+        if self.peek_op('^') {
+            self.expect_op('^')?;
+            // Right-associative: a^b^c == a^(b^c)
+            let exponent = self.parse_gen_power()?;
+            return self.build_gen_binary("pow", base, exponent);
+        }
+
+        Ok(base)
+    }
+
+    fn build_gen_product(&self, mut gens: Vec<GenBox>) -> Result<GenBox, Box<dyn Error>> {
         if gens.len() == 1 {
             return Ok(gens.pop().unwrap());
         }
@@ -338,6 +379,29 @@ impl<T: Iterator<Item = char>> Parser<T> {
         factory.new(&mut params).map_err(Into::into)
     }
 
+    fn build_gen_binary(
+        &self,
+        name: &str,
+        left: GenBox,
+        right: GenBox,
+    ) -> Result<GenBox, Box<dyn Error>> {
+        let mut params = FactoryParameters {
+            env: self.env.clone(),
+            ..Default::default()
+        };
+        params
+            .vars
+            .insert("0".to_string(), ParamValue::Generator(left));
+        params
+            .vars
+            .insert("1".to_string(), ParamValue::Generator(right));
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or(ErrorType::new(ErrorKind::UnknownGen(name.to_string())))?;
+        factory.new(&mut params).map_err(Into::into)
+    }
+
     pub fn parse_gen(&mut self) -> Result<GenBox, Box<dyn Error>> {
         match *self.cur_token() {
             Token::Integer(v) => {
@@ -375,8 +439,9 @@ impl<T: Iterator<Item = char>> Parser<T> {
             Token::Ident(_) => {
                 let name = self.expect_ident()?;
                 if self.peek_op('(') {
-                    let mut params = self.parse_factory_params()?;
-                    let factory = match self.factories.get(&name) {
+                    let factory = self.factories.get(&name).copied();
+                    let mut params = self.parse_call_params(factory)?;
+                    let factory = match factory {
                         Some(fac) => fac,
                         None => return Err(ErrorType::new(ErrorKind::UnknownGen(name)).into()),
                     };
@@ -412,6 +477,57 @@ impl<T: Iterator<Item = char>> Parser<T> {
         }
     }
 
+    // Parses a call's parameters, then parses them again with a buffer size of 1 if the factory
+    // asks for per-sample inputs, and again for each extra copy it asks for. The tokens of the
+    // first pass are recorded for the replays.
+    fn parse_call_params(
+        &mut self,
+        factory: Option<&'static dyn GeneratorFactory>,
+    ) -> Result<FactoryParameters, Box<dyn Error>> {
+        self.recording.push(Vec::new());
+        let params = self.parse_factory_params();
+        let tokens = self.recording.pop().unwrap();
+        let mut params = params?;
+        let factory = match factory {
+            Some(fac) => fac,
+            None => return Ok(params),
+        };
+
+        let size = self.env.default_buffer_size;
+        let inner_size = if factory.per_sample(&mut params) {
+            params = self.replay_factory_params(&tokens, 1)?;
+            params.env.default_buffer_size = size;
+            1
+        } else {
+            size
+        };
+        for _ in 1..factory.copies(&mut params) {
+            let mut copy = self.replay_factory_params(&tokens, inner_size)?;
+            copy.env.default_buffer_size = size;
+            params.copies.push(copy);
+        }
+        Ok(params)
+    }
+
+    // Parses the recorded parameter tokens again with the given buffer size, then carries on from
+    // the token that followed them.
+    fn replay_factory_params(
+        &mut self,
+        tokens: &[Token],
+        size: usize,
+    ) -> Result<FactoryParameters, Box<dyn Error>> {
+        // Enclosing recordings already hold these tokens, so they mustn't see them again.
+        let outer = mem::take(&mut self.recording);
+        let next = mem::replace(&mut self.token, tokens[0].clone());
+        self.replay.push(next);
+        self.replay.extend(tokens[1..].iter().rev().cloned());
+        let outer_size = mem::replace(&mut self.env.default_buffer_size, size);
+        let params = self.parse_factory_params();
+        self.env.default_buffer_size = outer_size;
+        self.recording = outer;
+        params
+    }
+
     pub fn parse_factory_params(&mut self) -> Result<FactoryParameters, Box<dyn Error>> {
         dprintln!("consuming paren in factory_params");
         self.expect_op('(')?;
@@ -48,6 +48,7 @@ fn main_client(args: Vec<ffi::OsString>) -> Result<(), std::io::Error> {
     let env = Environment {
         sample_rate: conf.sample_rate.0 as f32,
         default_buffer_size: 64,
+        ..Default::default()
     };
 
     let mut genfile = File::open(
@@ -67,14 +68,14 @@ fn main_client(args: Vec<ffi::OsString>) -> Result<(), std::io::Error> {
 
     eprintln!("Parsed {} generator definitions", gens.len());
 
-    let client = Arc::new(Mutex::new(
-        Client::new(
-            sock.try_clone().expect("Failed to clone socket"),
-            gens,
-            env.clone(),
-        )
-        .expect("Failed to create client"),
-    ));
+    let mut client = Client::new(
+        sock.try_clone().expect("Failed to clone socket"),
+        gens,
+        env.clone(),
+    )
+    .expect("Failed to create client");
+    client.limiter = Some(Limiter::new(-0.3, 0.005, 0.05, env.sample_rate));
+    let client = Arc::new(Mutex::new(client));
     let last_buffer = Arc::new(Mutex::new(<VecDeque<Sample>>::with_capacity(
         env.default_buffer_size * 9,
     )));
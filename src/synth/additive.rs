@@ -0,0 +1,89 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+const TAU: f32 = 2f32 * PI;
+
+// A bank of sine partials at `freq` times each ratio, partial k having amplitude amps[k]. Ratios
+// default to the harmonic series; partials at or above Nyquist are silent.
+#[derive(Debug)]
+pub struct Additive {
+    pub freq: GenBox,
+    pub amps: Vec<GenBox>,
+    pub ratios: Vec<f32>,
+    pub phases: Vec<f32>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Additive {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+        for v in self.buf.iter_mut() {
+            *v = 0.0;
+        }
+
+        let freq = self.freq.eval(params);
+        for (k, gen) in self.amps.iter_mut().enumerate() {
+            let amp = gen.eval(params);
+            let ratio = self.ratios[k];
+            let phase = &mut self.phases[k];
+            for i in 0..self.buf.len() {
+                let pvel = freq.value_at(i) * ratio / params.env.sample_rate;
+                if pvel.abs() < 0.5 {
+                    self.buf[i] += amp.value_at(i) * (TAU * *phase).sin();
+                }
+                *phase = (*phase + pvel).rem_euclid(1.0);
+            }
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct AdditiveFactory;
+
+impl GeneratorFactory for AdditiveFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let ratios = params
+            .vars
+            .remove("ratios")
+            .unwrap_or(ParamValue::String(String::new()))
+            .as_string()?;
+        let ratios: Vec<f32> = ratios
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|s| s.parse::<f32>().ok())
+            .collect();
+
+        let mut amps = Vec::new();
+        while let Ok(gen) = params.remove_param("_", 1 + amps.len()) {
+            amps.push(gen.into_gen()?);
+        }
+
+        if amps.is_empty() {
+            return Err(GenFactoryError::MissingRequiredParam(
+                "amplitudes".to_string(),
+                1,
+            ));
+        }
+
+        Ok(Box::new(Additive {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            ratios: (0..amps.len())
+                .map(|k| ratios.get(k).cloned().unwrap_or((k + 1) as f32))
+                .collect(),
+            phases: vec![0.0; amps.len()],
+            amps,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: AdditiveFactory = AdditiveFactory;
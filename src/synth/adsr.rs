@@ -1,10 +1,11 @@
 use super::{
-    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, Parameters, Rate,
-    SampleBuffer,
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Phase {
+    Idle,
     Delay,
     Attack,
     Hold,
@@ -13,6 +14,106 @@ pub enum Phase {
     Release,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Linear,
+    Exp,
+}
+
+impl Curve {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            Curve::Linear => "linear",
+            Curve::Exp => "exp",
+        }
+    }
+
+    // Moves `cur` one sample toward `target` for a segment taking `time` seconds over a full-scale
+    // (0 to 1) swing, returning the new level and whether the segment is finished. Exponential
+    // segments cover 60dB of the remaining distance in that time.
+    pub fn step(&self, cur: f32, target: f32, time: f32, sample_rate: f32) -> (f32, bool) {
+        let samples = time * sample_rate;
+        if samples <= 1.0 {
+            return (target, true);
+        }
+        let next = match *self {
+            Curve::Linear => {
+                let rate = 1.0 / samples;
+                cur + (target - cur).max(-rate).min(rate)
+            }
+            Curve::Exp => target + (cur - target) * (-6.908 / samples).exp(),
+        };
+        if (target - next).abs() < 1.0e-4 {
+            (target, true)
+        } else {
+            (next, false)
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Curve {
+    fn from(s: &'a str) -> Curve {
+        match s {
+            "exp" | "exponential" => Curve::Exp,
+            _ => Curve::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retrigger {
+    Reset,
+    Legato,
+}
+
+impl Retrigger {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            Retrigger::Reset => "reset",
+            Retrigger::Legato => "legato",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Retrigger {
+    fn from(s: &'a str) -> Retrigger {
+        if s == "legato" {
+            Retrigger::Legato
+        } else {
+            Retrigger::Reset
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Level,
+    Done,
+}
+
+impl Output {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            Output::Level => "level",
+            Output::Done => "done",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Output {
+    fn from(s: &'a str) -> Output {
+        if s == "done" {
+            Output::Done
+        } else {
+            Output::Level
+        }
+    }
+}
+
+// Times are in seconds. A rising gate restarts the envelope from the delay segment at whatever
+// level it is currently at; with legato retriggering, a gate that arrives before the release has
+// finished skips straight to the decay segment instead. Output 'done' gives 1 while the envelope
+// is idle (before the first gate, and after each release completes) and 0 otherwise.
 #[derive(Debug)]
 pub struct DAHDSR {
     pub delay: GenBox,
@@ -22,10 +123,15 @@ pub struct DAHDSR {
     pub sustain: GenBox,
     pub release: GenBox,
     pub gate: GenBox,
+    pub attack_curve: Curve,
+    pub decay_curve: Curve,
+    pub release_curve: Curve,
+    pub retrigger: Retrigger,
+    pub output: Output,
     pub phase: Phase,
     pub cur: f32,
-    pub attack_cd: f32,
-    pub decay_cd: f32,
+    pub countdown: f32,
+    pub gated: bool,
     pub buf: SampleBuffer,
 }
 
@@ -33,50 +139,55 @@ impl Generator for DAHDSR {
     fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
         self.buf.rate = Rate::Sample;
 
+        let sr = params.env.sample_rate;
         let delay = self.delay.eval(params).first();
         let attack = self.attack.eval(params).first();
-        let hold = self.attack.eval(params).first();
+        let hold = self.hold.eval(params).first();
         let decay = self.decay.eval(params).first();
         let sustain = self.sustain.eval(params).first();
         let release = self.release.eval(params).first();
-        let gate = self.gate.eval(params).first();
+        let gate = self.gate.eval(params);
 
-        if gate >= 0.5 {
-            if self.phase == Phase::Release {
-                self.phase = Phase::Delay;
-                self.attack_cd = delay;
-                self.cur = 0.0;
+        for i in 0..self.buf.len() {
+            let gated = gate.value_at(i) >= 0.5;
+            if gated && !self.gated {
+                if self.retrigger == Retrigger::Legato && self.phase != Phase::Idle {
+                    self.phase = Phase::Decay;
+                } else {
+                    self.phase = Phase::Delay;
+                    self.countdown = delay;
+                }
+            } else if !gated && self.phase != Phase::Idle {
+                self.phase = Phase::Release;
             }
-        } else {
-            self.phase = Phase::Release;
-        }
+            self.gated = gated;
 
-        for samp in self.buf.samples.iter_mut() {
             match self.phase {
+                Phase::Idle => (),
                 Phase::Delay => {
-                    self.attack_cd -= 1.0;
-                    if self.attack_cd <= 0.0 {
+                    self.countdown -= 1.0 / sr;
+                    if self.countdown <= 0.0 {
                         self.phase = Phase::Attack;
                     }
                 }
                 Phase::Attack => {
-                    self.cur += attack;
-                    if self.cur >= 1.0 {
-                        self.cur = 1.0;
+                    let (cur, done) = self.attack_curve.step(self.cur, 1.0, attack, sr);
+                    self.cur = cur;
+                    if done {
                         self.phase = Phase::Hold;
-                        self.decay_cd = hold;
+                        self.countdown = hold;
                     }
                 }
                 Phase::Hold => {
-                    self.decay_cd -= 1.0;
-                    if self.decay_cd <= 0.0 {
+                    self.countdown -= 1.0 / sr;
+                    if self.countdown <= 0.0 {
                         self.phase = Phase::Decay;
                     }
                 }
                 Phase::Decay => {
-                    self.cur -= decay;
-                    if self.cur <= sustain {
-                        self.cur = sustain;
+                    let (cur, done) = self.decay_curve.step(self.cur, sustain, decay, sr);
+                    self.cur = cur;
+                    if done {
                         self.phase = Phase::Sustain;
                     }
                 }
@@ -84,13 +195,18 @@ impl Generator for DAHDSR {
                     self.cur = sustain;
                 }
                 Phase::Release => {
-                    self.cur -= release;
-                    if self.cur < 0.0 {
-                        self.cur = 0.0;
+                    let (cur, done) = self.release_curve.step(self.cur, 0.0, release, sr);
+                    self.cur = cur;
+                    if done {
+                        self.phase = Phase::Idle;
                     }
                 }
             }
-            *samp = self.cur;
+
+            self.buf[i] = match self.output {
+                Output::Level => self.cur,
+                Output::Done => (self.phase == Phase::Idle) as u8 as f32,
+            };
         }
 
         &self.buf
@@ -103,10 +219,27 @@ impl Generator for DAHDSR {
     }
 }
 
+fn string_var(
+    params: &mut FactoryParameters,
+    name: &str,
+    default: &str,
+) -> Result<String, GenFactoryError> {
+    params
+        .vars
+        .remove(name)
+        .unwrap_or_else(|| ParamValue::String(default.to_string()))
+        .as_string()
+}
+
 pub struct DAHDSRFactory;
 
 impl GeneratorFactory for DAHDSRFactory {
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let attack_curve = string_var(params, "attack_curve", "linear")?;
+        let decay_curve = string_var(params, "decay_curve", "linear")?;
+        let release_curve = string_var(params, "release_curve", "linear")?;
+        let retrigger = string_var(params, "retrigger", "reset")?;
+        let output = string_var(params, "output", "level")?;
         Ok(Box::new(DAHDSR {
             delay: params.remove_param("delay", 1)?.into_gen()?,
             attack: params.remove_param("attack", 2)?.into_gen()?,
@@ -115,10 +248,15 @@ impl GeneratorFactory for DAHDSRFactory {
             sustain: params.remove_param("sustain", 5)?.into_gen()?,
             release: params.remove_param("release", 6)?.into_gen()?,
             gate: params.remove_param("gate", 0)?.into_gen()?,
-            phase: Phase::Release,
+            attack_curve: (&*attack_curve).into(),
+            decay_curve: (&*decay_curve).into(),
+            release_curve: (&*release_curve).into(),
+            retrigger: (&*retrigger).into(),
+            output: (&*output).into(),
+            phase: Phase::Idle,
             cur: 0.0,
-            attack_cd: 0.0,
-            decay_cd: 0.0,
+            countdown: 0.0,
+            gated: false,
             buf: SampleBuffer::new(params.env.default_buffer_size),
         }))
     }
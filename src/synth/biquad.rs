@@ -0,0 +1,222 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BiquadMode {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    Peak,
+    LowShelf,
+    HighShelf,
+    AllPass,
+}
+
+impl BiquadMode {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            BiquadMode::LowPass => "lowpass",
+            BiquadMode::HighPass => "highpass",
+            BiquadMode::BandPass => "bandpass",
+            BiquadMode::Notch => "notch",
+            BiquadMode::Peak => "peak",
+            BiquadMode::LowShelf => "lowshelf",
+            BiquadMode::HighShelf => "highshelf",
+            BiquadMode::AllPass => "allpass",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for BiquadMode {
+    fn from(s: &'a str) -> BiquadMode {
+        match s {
+            "highpass" | "hp" => BiquadMode::HighPass,
+            "bandpass" | "bp" => BiquadMode::BandPass,
+            "notch" => BiquadMode::Notch,
+            "peak" => BiquadMode::Peak,
+            "lowshelf" => BiquadMode::LowShelf,
+            "highshelf" => BiquadMode::HighShelf,
+            "allpass" | "ap" => BiquadMode::AllPass,
+            _ => BiquadMode::LowPass,
+        }
+    }
+}
+
+// Coefficients from the RBJ Audio EQ Cookbook, normalized so that a0 == 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoeffs {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl BiquadCoeffs {
+    pub fn new(mode: BiquadMode, freq: f32, q: f32, gain: f32, sample_rate: f32) -> BiquadCoeffs {
+        let freq = freq.max(1.0).min(0.49 * sample_rate);
+        let q = q.max(1.0e-3);
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10f32.powf(gain / 40.0);
+        let sq = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match mode {
+            BiquadMode::LowPass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            BiquadMode::HighPass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            BiquadMode::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            BiquadMode::Notch => (1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            BiquadMode::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            BiquadMode::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + sq),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sq),
+                (a + 1.0) + (a - 1.0) * cos + sq,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sq,
+            ),
+            BiquadMode::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + sq),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - sq),
+                (a + 1.0) - (a - 1.0) * cos + sq,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - sq,
+            ),
+            BiquadMode::AllPass => (
+                1.0 - alpha,
+                -2.0 * cos,
+                1.0 + alpha,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+        };
+
+        BiquadCoeffs {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+// Direct Form I history; kept apart from the coefficients so banks of filters can share code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BiquadState {
+    pub x1: f32,
+    pub x2: f32,
+    pub y1: f32,
+    pub y2: f32,
+}
+
+impl BiquadState {
+    pub fn process(&mut self, c: &BiquadCoeffs, x: Sample) -> Sample {
+        let y = c.b0 * x + c.b1 * self.x1 + c.b2 * self.x2 - c.a1 * self.y1 - c.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+#[derive(Debug)]
+pub struct Biquad {
+    pub input: GenBox,
+    pub cutoff: GenBox,
+    pub q: GenBox,
+    pub gain: GenBox,
+    pub mode: BiquadMode,
+    pub coeffs: BiquadCoeffs,
+    pub state: BiquadState,
+    pub last: (f32, f32, f32),
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Biquad {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let cutoff = self.cutoff.eval(params);
+        let q = self.q.eval(params);
+        let gain = self.gain.eval(params);
+        for i in 0..self.buf.len() {
+            let ctl = (cutoff.value_at(i), q.value_at(i), gain.value_at(i));
+            if ctl != self.last {
+                self.coeffs =
+                    BiquadCoeffs::new(self.mode, ctl.0, ctl.1, ctl.2, params.env.sample_rate);
+                self.last = ctl;
+            }
+            self.buf[i] = self.state.process(&self.coeffs, input.value_at(i));
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct BiquadFactory;
+
+impl GeneratorFactory for BiquadFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mode = params
+            .get_param("mode", 1, &mut ParamValue::String("lowpass".to_string()))
+            .as_string()?;
+        let mode: BiquadMode = (&*mode).into();
+        Ok(Box::new(Biquad {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            cutoff: params.remove_param("cutoff", 2)?.into_gen()?,
+            q: params
+                .remove_param("q", 3)
+                .unwrap_or(ParamValue::Float(FRAC_1_SQRT_2))
+                .into_gen()?,
+            gain: params
+                .remove_param("gain", 4)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            mode,
+            coeffs: BiquadCoeffs::new(mode, 1000.0, FRAC_1_SQRT_2, 0.0, params.env.sample_rate),
+            state: Default::default(),
+            // NaN never compares equal, so the first sample always computes real coefficients.
+            last: (f32::NAN, f32::NAN, f32::NAN),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: BiquadFactory = BiquadFactory;
@@ -0,0 +1,245 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+// PolyBLEP residual of a unit-height step (scaled by 2), t and dt in cycles.
+pub fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = t / dt;
+        2.0 * x - x * x - 1.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt;
+        x * x + 2.0 * x + 1.0
+    } else {
+        0.0
+    }
+}
+
+// PolyBLAMP residual of a unit change in slope (per sample), t and dt in cycles.
+pub fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = 1.0 - t / dt;
+        x * x * x / 6.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt + 1.0;
+        x * x * x / 6.0
+    } else {
+        0.0
+    }
+}
+
+pub fn bl_saw(ph: f32, dt: f32) -> Sample {
+    2.0 * ph - 1.0 - poly_blep(ph, dt)
+}
+
+pub fn bl_square(ph: f32, width: f32, dt: f32) -> Sample {
+    // Keep both edges at least a sample apart, or their residuals would overlap.
+    let width = width.max(dt).min(1.0 - dt);
+    let naive = if ph < width { -1.0 } else { 1.0 };
+    naive - poly_blep(ph, dt) + poly_blep((ph - width + 1.0) % 1.0, dt)
+}
+
+pub fn bl_triangle(ph: f32, dt: f32) -> Sample {
+    let naive = if ph < 0.25 {
+        4.0 * ph
+    } else if ph > 0.75 {
+        4.0 * ph - 4.0
+    } else {
+        -4.0 * ph + 2.0
+    };
+    naive - 8.0 * dt * poly_blamp((ph + 0.75) % 1.0, dt)
+        + 8.0 * dt * poly_blamp((ph + 0.25) % 1.0, dt)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OscShape {
+    Sine,
+    Saw,
+    Square,
+    Triangle,
+}
+
+impl OscShape {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            OscShape::Sine => "sine",
+            OscShape::Saw => "saw",
+            OscShape::Square => "square",
+            OscShape::Triangle => "triangle",
+        }
+    }
+
+    // One band-limited sample at phase `ph` (in cycles); `width` only applies to squares.
+    pub fn sample(&self, ph: f32, width: f32, dt: f32) -> Sample {
+        match *self {
+            OscShape::Sine => (2.0 * PI * ph).sin(),
+            OscShape::Saw => bl_saw(ph, dt),
+            OscShape::Square => bl_square(ph, width, dt),
+            OscShape::Triangle => bl_triangle(ph, dt),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for OscShape {
+    fn from(s: &'a str) -> OscShape {
+        match s {
+            "sine" => OscShape::Sine,
+            "square" => OscShape::Square,
+            "triangle" | "tri" => OscShape::Triangle,
+            _ => OscShape::Saw,
+        }
+    }
+}
+
+// The residual width is clamped away from zero and Nyquist so the corrections stay well-formed.
+// A NaN frequency maps to the narrowest width rather than poisoning the residuals.
+pub fn residual_width(pvel: f32) -> f32 {
+    if pvel.is_nan() {
+        1.0e-6
+    } else {
+        pvel.abs().clamp(1.0e-6, 0.5)
+    }
+}
+
+#[derive(Debug)]
+pub struct BlSaw {
+    pub freq: GenBox,
+    pub phase: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for BlSaw {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        for i in 0..self.buf.len() {
+            let pvel = freq.value_at(i) / params.env.sample_rate;
+            let dt = residual_width(pvel);
+            self.buf[i] = bl_saw(self.phase, dt);
+            self.phase = (self.phase + pvel).rem_euclid(1.0);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct BlSawFactory;
+
+impl GeneratorFactory for BlSawFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(BlSaw {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            phase: params
+                .get_param("phase", 1, &mut ParamValue::Float(0.0))
+                .as_f32()?,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryBlSaw: BlSawFactory = BlSawFactory;
+
+#[derive(Debug)]
+pub struct BlSquare {
+    pub freq: GenBox,
+    pub width: GenBox,
+    pub phase: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for BlSquare {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        let width = self.width.eval(params);
+        for i in 0..self.buf.len() {
+            let pvel = freq.value_at(i) / params.env.sample_rate;
+            let dt = residual_width(pvel);
+            self.buf[i] = bl_square(self.phase, width.value_at(i), dt);
+            self.phase = (self.phase + pvel).rem_euclid(1.0);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct BlSquareFactory;
+
+impl GeneratorFactory for BlSquareFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(BlSquare {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            width: params
+                .remove_param("width", 2)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            phase: params
+                .get_param("phase", 1, &mut ParamValue::Float(0.0))
+                .as_f32()?,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryBlSquare: BlSquareFactory = BlSquareFactory;
+
+#[derive(Debug)]
+pub struct BlTriangle {
+    pub freq: GenBox,
+    pub phase: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for BlTriangle {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        for i in 0..self.buf.len() {
+            let pvel = freq.value_at(i) / params.env.sample_rate;
+            let dt = residual_width(pvel);
+            self.buf[i] = bl_triangle(self.phase, dt);
+            self.phase = (self.phase + pvel).rem_euclid(1.0);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct BlTriangleFactory;
+
+impl GeneratorFactory for BlTriangleFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(BlTriangle {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            phase: params
+                .get_param("phase", 1, &mut ParamValue::Float(0.0))
+                .as_f32()?,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryBlTriangle: BlTriangleFactory = BlTriangleFactory;
@@ -0,0 +1,109 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+
+// A circular history of samples. Taps are read before the current sample is pushed, so the
+// shortest meaningful delay is one sample.
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    pub hist: Vec<Sample>,
+    pub pos: usize,
+}
+
+impl DelayLine {
+    pub fn new(len: usize) -> DelayLine {
+        DelayLine {
+            hist: vec![0.0; len.max(2)],
+            pos: 0,
+        }
+    }
+
+    pub fn push(&mut self, val: Sample) {
+        self.hist[self.pos] = val;
+        self.pos = (self.pos + 1) % self.hist.len();
+    }
+
+    pub fn tap_int(&self, delay: usize) -> Sample {
+        let len = self.hist.len();
+        self.hist[(self.pos + len - delay.max(1).min(len)) % len]
+    }
+
+    // Linearly interpolated read, delay in (fractional) samples.
+    pub fn tap(&self, delay: f32) -> Sample {
+        let delay = delay.max(1.0).min((self.hist.len() - 1) as f32);
+        let whole = delay.floor();
+        let frac = delay - whole;
+        let a = self.tap_int(whole as usize);
+        let b = self.tap_int(whole as usize + 1);
+        a + (b - a) * frac
+    }
+
+    pub fn clear(&mut self) {
+        for v in self.hist.iter_mut() {
+            *v = 0.0;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Delay {
+    pub input: GenBox,
+    pub time: GenBox,
+    pub feedback: GenBox,
+    pub mix: GenBox,
+    pub line: DelayLine,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Delay {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let time = self.time.eval(params);
+        let feedback = self.feedback.eval(params);
+        let mix = self.mix.eval(params);
+        for i in 0..self.buf.len() {
+            let dry = input.value_at(i);
+            let wet = self.line.tap(time.value_at(i) * params.env.sample_rate);
+            self.line.push(dry + wet * feedback.value_at(i));
+            let mix = mix.value_at(i);
+            self.buf[i] = dry * (1.0 - mix) + wet * mix;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct DelayFactory;
+
+impl GeneratorFactory for DelayFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let max = params
+            .get_param("max", 4, &mut ParamValue::Float(1.0))
+            .as_f32()?;
+        Ok(Box::new(Delay {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            time: params.remove_param("time", 1)?.into_gen()?,
+            feedback: params
+                .remove_param("feedback", 2)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            mix: params
+                .remove_param("mix", 3)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            line: DelayLine::new((max * params.env.sample_rate).ceil() as usize + 2),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: DelayFactory = DelayFactory;
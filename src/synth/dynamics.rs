@@ -0,0 +1,326 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::collections::VecDeque;
+
+use super::delay::DelayLine;
+
+// One-pole coefficient that covers ~63% of a step in `time` seconds; 0 means "follow instantly".
+pub fn time_coef(time: f32, sample_rate: f32) -> f32 {
+    let samples = time * sample_rate;
+    if samples <= 1.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+pub fn db_to_amp(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+pub fn amp_to_db(amp: f32) -> f32 {
+    20.0 * amp.max(1.0e-6).log10()
+}
+
+// Peak envelope detector with separate attack and release smoothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FollowerState {
+    pub level: f32,
+}
+
+impl FollowerState {
+    pub fn process(&mut self, x: Sample, attack_coef: f32, release_coef: f32) -> f32 {
+        let x = x.abs();
+        let coef = if x > self.level {
+            attack_coef
+        } else {
+            release_coef
+        };
+        self.level = x + (self.level - x) * coef;
+        self.level
+    }
+}
+
+#[derive(Debug)]
+pub struct Follower {
+    pub input: GenBox,
+    pub attack: GenBox,
+    pub release: GenBox,
+    pub state: FollowerState,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Follower {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let sr = params.env.sample_rate;
+        let input = self.input.eval(params);
+        let attack = time_coef(self.attack.eval(params).first(), sr);
+        let release = time_coef(self.release.eval(params).first(), sr);
+        for i in 0..self.buf.len() {
+            self.buf[i] = self.state.process(input.value_at(i), attack, release);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct FollowerFactory;
+
+impl GeneratorFactory for FollowerFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Follower {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            attack: params
+                .remove_param("attack", 1)
+                .unwrap_or(ParamValue::Float(0.01))
+                .into_gen()?,
+            release: params
+                .remove_param("release", 2)
+                .unwrap_or(ParamValue::Float(0.1))
+                .into_gen()?,
+            state: Default::default(),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryFollower: FollowerFactory = FollowerFactory;
+
+// Feed-forward compressor. The level of `sidechain` (the input itself if not given) above
+// `threshold` dB is reduced by `ratio`, and `makeup` dB of gain is applied afterward.
+#[derive(Debug)]
+pub struct Compressor {
+    pub input: GenBox,
+    pub sidechain: Option<GenBox>,
+    pub threshold: GenBox,
+    pub ratio: GenBox,
+    pub attack: GenBox,
+    pub release: GenBox,
+    pub makeup: GenBox,
+    pub detector: FollowerState,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Compressor {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let sr = params.env.sample_rate;
+        let input = self.input.eval(params);
+        let sidechain = self
+            .sidechain
+            .as_mut()
+            .map(|s| s.eval(params))
+            .unwrap_or(input);
+        let threshold = self.threshold.eval(params);
+        let ratio = self.ratio.eval(params);
+        let makeup = self.makeup.eval(params);
+        let attack = time_coef(self.attack.eval(params).first(), sr);
+        let release = time_coef(self.release.eval(params).first(), sr);
+        for i in 0..self.buf.len() {
+            let level = amp_to_db(
+                self.detector
+                    .process(sidechain.value_at(i), attack, release),
+            );
+            let over = (level - threshold.value_at(i)).max(0.0);
+            let reduction = over * (1.0 - 1.0 / ratio.value_at(i).max(1.0));
+            self.buf[i] = input.value_at(i) * db_to_amp(makeup.value_at(i) - reduction);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct CompressorFactory;
+
+impl GeneratorFactory for CompressorFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let sidechain = match params.remove_param("sidechain", 6) {
+            Ok(pv) => Some(pv.into_gen()?),
+            Err(_) => None,
+        };
+        Ok(Box::new(Compressor {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            sidechain,
+            threshold: params
+                .remove_param("threshold", 1)
+                .unwrap_or(ParamValue::Float(-20.0))
+                .into_gen()?,
+            ratio: params
+                .remove_param("ratio", 2)
+                .unwrap_or(ParamValue::Float(4.0))
+                .into_gen()?,
+            attack: params
+                .remove_param("attack", 3)
+                .unwrap_or(ParamValue::Float(0.005))
+                .into_gen()?,
+            release: params
+                .remove_param("release", 4)
+                .unwrap_or(ParamValue::Float(0.1))
+                .into_gen()?,
+            makeup: params
+                .remove_param("makeup", 5)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            detector: Default::default(),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryCompressor: CompressorFactory = CompressorFactory;
+
+// Brick-wall lookahead limiter. The gain each sample needs to stay under the ceiling is
+// min-held over the lookahead window and then box-filtered over the same window, so the gain has
+// fully ramped down by the time the (delayed) peak reaches the output. Recovery is further slowed
+// by a one-pole release, which can only lower the gain and so never lets a peak through.
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    pub ceiling: f32,
+    pub release_coef: f32,
+    pub window: usize,
+    pub line: DelayLine,
+    pub held: VecDeque<(usize, f32)>,
+    pub boxcar: Vec<f32>,
+    pub sum: f64,
+    pub gain: f32,
+    pub count: usize,
+}
+
+impl Limiter {
+    pub fn new(ceiling_db: f32, lookahead: f32, release: f32, sample_rate: f32) -> Limiter {
+        let window = ((lookahead * sample_rate) as usize).max(1);
+        Limiter {
+            ceiling: db_to_amp(ceiling_db),
+            release_coef: time_coef(release, sample_rate),
+            window,
+            line: DelayLine::new(window + 1),
+            held: VecDeque::with_capacity(window + 1),
+            boxcar: vec![1.0; window],
+            sum: window as f64,
+            gain: 1.0,
+            count: 0,
+        }
+    }
+
+    // The output lags the input by `window - 1` samples.
+    pub fn process(&mut self, x: Sample) -> Sample {
+        let need = (self.ceiling / x.abs().max(1.0e-9)).min(1.0);
+        while let Some(&(_, g)) = self.held.back() {
+            if g < need {
+                break;
+            }
+            self.held.pop_back();
+        }
+        self.held.push_back((self.count, need));
+        while let Some(&(idx, _)) = self.held.front() {
+            if idx + self.window > self.count {
+                break;
+            }
+            self.held.pop_front();
+        }
+        let min = self.held.front().map(|&(_, g)| g).unwrap_or(1.0);
+
+        let slot = self.count % self.window;
+        self.sum += (min - self.boxcar[slot]) as f64;
+        self.boxcar[slot] = min;
+        if slot == 0 {
+            // Resum once per window so rounding in the running sum can't accumulate.
+            self.sum = self.boxcar.iter().map(|&g| g as f64).sum();
+        }
+        let target = ((self.sum / self.window as f64) as f32).min(1.0);
+        self.gain = if target < self.gain {
+            target
+        } else {
+            target + (self.gain - target) * self.release_coef
+        };
+        self.count += 1;
+
+        let delayed = if self.window > 1 {
+            self.line.tap_int(self.window - 1)
+        } else {
+            x
+        };
+        self.line.push(x);
+        delayed * self.gain
+    }
+
+    // Limits `buf` in place; the result is always sample-rate.
+    pub fn process_buffer(&mut self, buf: &mut SampleBuffer) {
+        if buf.rate == Rate::Control {
+            let val = buf.first();
+            for samp in buf.iter_mut() {
+                *samp = val;
+            }
+            buf.rate = Rate::Sample;
+        }
+        for samp in buf.iter_mut() {
+            *samp = self.process(*samp);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Limit {
+    pub input: GenBox,
+    pub limiter: Limiter,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Limit {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        for i in 0..self.buf.len() {
+            self.buf[i] = self.limiter.process(input.value_at(i));
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct LimitFactory;
+
+impl GeneratorFactory for LimitFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let ceiling = params
+            .get_param("ceiling", 1, &mut ParamValue::Float(-0.3))
+            .as_f32()?;
+        let lookahead = params
+            .get_param("lookahead", 2, &mut ParamValue::Float(0.005))
+            .as_f32()?;
+        let release = params
+            .get_param("release", 3, &mut ParamValue::Float(0.05))
+            .as_f32()?;
+        Ok(Box::new(Limit {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            limiter: Limiter::new(ceiling, lookahead, release, params.env.sample_rate),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryLimit: LimitFactory = LimitFactory;
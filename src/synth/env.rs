@@ -0,0 +1,180 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, Parameters, Rate,
+    Sample, SampleBuffer,
+};
+
+// One segment of an envelope: move to `level` over `time` seconds. `curve` follows the
+// SuperCollider convention: 0 is linear, positive values start slowly and finish quickly, and
+// negative values the reverse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    pub level: f32,
+    pub time: f32,
+    pub curve: f32,
+}
+
+impl Breakpoint {
+    // Level at fraction `t` (0 to 1) of the way through this segment, starting from `from`.
+    pub fn interpolate(&self, from: Sample, t: f32) -> Sample {
+        if self.curve.abs() < 1.0e-3 {
+            from + (self.level - from) * t
+        } else {
+            from + (self.level - from) * (1.0 - (self.curve * t).exp()) / (1.0 - self.curve.exp())
+        }
+    }
+}
+
+// Runs through `points` from the start on each rising edge of `gate`, beginning at the current
+// level. While the gate is held the envelope stops at the end of the `sustain` breakpoint, or
+// cycles from the end of `loopend` back to the start of `loopstart`; when the gate falls it
+// carries on after whichever of those comes last. Without a sustain or loop the envelope ignores
+// the gate falling and simply runs to the end.
+#[derive(Debug)]
+pub struct Envelope {
+    pub gate: GenBox,
+    pub points: Vec<Breakpoint>,
+    pub sustain: Option<usize>,
+    pub loop_points: Option<(usize, usize)>,
+    pub state: EnvelopeState,
+    pub buf: SampleBuffer,
+}
+
+impl Envelope {
+    fn release_point(&self) -> Option<usize> {
+        match (self.sustain, self.loop_points) {
+            (Some(s), Some((_, e))) => Some(s.max(e)),
+            (Some(s), None) => Some(s),
+            (None, Some((_, e))) => Some(e),
+            (None, None) => None,
+        }
+    }
+}
+
+// Position within the breakpoints; `seg` past the end means the envelope has finished.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvelopeState {
+    pub seg: usize,
+    pub pos: f32,
+    pub from: Sample,
+    pub cur: Sample,
+    pub gated: bool,
+}
+
+impl EnvelopeState {
+    fn enter(&mut self, seg: usize) {
+        self.seg = seg;
+        self.pos = 0.0;
+        self.from = self.cur;
+    }
+}
+
+impl Generator for Envelope {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let release = self.release_point();
+        let gate = self.gate.eval(params);
+        let st = &mut self.state;
+        let dt = 1.0 / params.env.sample_rate;
+        for i in 0..self.buf.len() {
+            let gated = gate.value_at(i) >= 0.5;
+            if gated && !st.gated {
+                st.enter(0);
+            } else if !gated && st.gated {
+                if let Some(r) = release {
+                    if st.seg <= r {
+                        st.enter(r + 1);
+                    }
+                }
+            }
+            st.gated = gated;
+
+            if let Some(&point) = self.points.get(st.seg) {
+                let held = gated && self.sustain == Some(st.seg);
+                if !held || st.pos < point.time {
+                    st.pos += dt;
+                }
+                if st.pos >= point.time {
+                    st.cur = point.level;
+                    match self.loop_points {
+                        Some((s, e)) if gated && st.seg == e => st.enter(s),
+                        _ if held => st.pos = point.time,
+                        _ => st.enter(st.seg + 1),
+                    }
+                } else {
+                    st.cur = point.interpolate(st.from, st.pos / point.time);
+                }
+            }
+            self.buf[i] = st.cur;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+fn index_var(params: &mut FactoryParameters, name: &str) -> Result<Option<usize>, GenFactoryError> {
+    match params.vars.remove(name) {
+        Some(mut pv) => {
+            let idx = pv.as_isize()?;
+            Ok(if idx < 0 { None } else { Some(idx as usize) })
+        }
+        None => Ok(None),
+    }
+}
+
+pub struct EnvelopeFactory;
+
+impl GeneratorFactory for EnvelopeFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mut points: Vec<Breakpoint> = Vec::new();
+        while let Ok(level) = params
+            .get_req_param("_", 1 + 3 * points.len())
+            .and_then(|pv| pv.as_f32())
+        {
+            let pos = 1 + 3 * points.len();
+            points.push(Breakpoint {
+                level,
+                time: params.get_req_param("_", pos + 1)?.as_f32()?.max(0.0),
+                curve: params.get_req_param("_", pos + 2)?.as_f32()?,
+            });
+        }
+
+        if points.is_empty() {
+            return Err(GenFactoryError::MissingRequiredParam(
+                "breakpoints".to_string(),
+                1,
+            ));
+        }
+
+        let last = points.len() - 1;
+        let sustain = index_var(params, "sustain")?.map(|s| s.min(last));
+        let loop_points = match (
+            index_var(params, "loopstart")?,
+            index_var(params, "loopend")?,
+        ) {
+            (Some(s), Some(e)) if s <= e && s <= last => Some((s, e.min(last))),
+            (Some(s), None) if s <= last => Some((s, last)),
+            _ => None,
+        };
+
+        Ok(Box::new(Envelope {
+            gate: params.remove_param("gate", 0)?.into_gen()?,
+            sustain,
+            loop_points,
+            state: EnvelopeState {
+                seg: points.len(),
+                ..Default::default()
+            },
+            points,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: EnvelopeFactory = EnvelopeFactory;
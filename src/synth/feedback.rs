@@ -0,0 +1,129 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Named buffers shared between `fbwrite` and `fbread` generators. The parser gives each element
+// of a generator vector a fresh bus, so names are local to one voice.
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackBus(Arc<Mutex<HashMap<String, Arc<Mutex<SampleBuffer>>>>>);
+
+impl FeedbackBus {
+    pub fn slot(&self, name: &str, size: usize) -> Arc<Mutex<SampleBuffer>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(SampleBuffer::new(size))))
+            .clone()
+    }
+}
+
+// Passes `gen` through unchanged, publishing each block to the named slot afterward. With
+// `mode="sample"`, `gen` is built with a buffer size of 1 and evaluated once per sample, and the
+// previous sample is published before each evaluation, so a loop through it is delayed by one
+// sample instead of one block.
+#[derive(Debug)]
+pub struct FeedbackWrite {
+    pub gen: GenBox,
+    pub slot: Arc<Mutex<SampleBuffer>>,
+    pub per_sample: bool,
+    pub last: Sample,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for FeedbackWrite {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        if self.per_sample {
+            self.buf.rate = Rate::Sample;
+            for i in 0..self.buf.len() {
+                self.slot.lock().unwrap().set(self.last);
+                self.last = self.gen.eval(params).first();
+                self.buf[i] = self.last;
+            }
+        } else {
+            self.buf.update_from(self.gen.eval(params));
+        }
+        self.slot.lock().unwrap().update_from(&self.buf);
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct FeedbackWriteFactory;
+
+impl GeneratorFactory for FeedbackWriteFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let name = params.get_req_param("name", 0)?.as_string()?;
+        let per_sample = self.per_sample(params);
+        let mut gen = params.remove_param("gen", 1)?.into_gen()?;
+        if per_sample {
+            gen.set_buffer(SampleBuffer::new(1));
+        }
+        Ok(Box::new(FeedbackWrite {
+            gen,
+            slot: params
+                .env
+                .feedback
+                .slot(&name, params.env.default_buffer_size),
+            per_sample,
+            last: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+
+    fn per_sample(&self, params: &mut FactoryParameters) -> bool {
+        match params.get_param("mode", 2, &mut ParamValue::String("block".to_string())) {
+            ParamValue::String(mode) => mode == "sample",
+            _ => false,
+        }
+    }
+}
+
+pub static FactoryFeedbackWrite: FeedbackWriteFactory = FeedbackWriteFactory;
+
+// Yields whatever the matching `fbwrite` last published (silence before the first write). Read
+// from inside the written expression, that is the previous block, or the previous sample in
+// per-sample mode. A read evaluated after the write sees the current block instead.
+#[derive(Debug)]
+pub struct FeedbackRead {
+    pub slot: Arc<Mutex<SampleBuffer>>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for FeedbackRead {
+    fn eval<'a>(&'a mut self, _params: &Parameters) -> &'a SampleBuffer {
+        self.buf.update_from(&self.slot.lock().unwrap());
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct FeedbackReadFactory;
+
+impl GeneratorFactory for FeedbackReadFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let name = params.get_req_param("name", 0)?.as_string()?;
+        Ok(Box::new(FeedbackRead {
+            slot: params
+                .env
+                .feedback
+                .slot(&name, params.env.default_buffer_size),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryFeedbackRead: FeedbackReadFactory = FeedbackReadFactory;
@@ -0,0 +1,263 @@
+use super::biquad::{BiquadCoeffs, BiquadMode, BiquadState};
+use super::dynamics::{time_coef, FollowerState};
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+
+const FORMANTS: usize = 5;
+
+// (center Hz, bandwidth Hz, gain dB) for each formant of a bass voice singing a, e, i, o, u.
+const VOWELS: [[(f32, f32, f32); FORMANTS]; 5] = [
+    [
+        (600.0, 60.0, 0.0),
+        (1040.0, 70.0, -7.0),
+        (2250.0, 110.0, -9.0),
+        (2450.0, 120.0, -9.0),
+        (2750.0, 130.0, -20.0),
+    ],
+    [
+        (400.0, 40.0, 0.0),
+        (1620.0, 80.0, -12.0),
+        (2400.0, 100.0, -9.0),
+        (2800.0, 120.0, -12.0),
+        (3100.0, 120.0, -18.0),
+    ],
+    [
+        (250.0, 60.0, 0.0),
+        (1750.0, 90.0, -30.0),
+        (2600.0, 100.0, -16.0),
+        (3050.0, 120.0, -22.0),
+        (3340.0, 120.0, -28.0),
+    ],
+    [
+        (400.0, 40.0, 0.0),
+        (750.0, 80.0, -11.0),
+        (2400.0, 100.0, -21.0),
+        (2600.0, 120.0, -20.0),
+        (2900.0, 120.0, -40.0),
+    ],
+    [
+        (350.0, 40.0, 0.0),
+        (600.0, 80.0, -20.0),
+        (2400.0, 100.0, -32.0),
+        (2675.0, 120.0, -28.0),
+        (2950.0, 120.0, -36.0),
+    ],
+];
+
+// Formant k of the vowel morph position `vowel` (0 = a through 4 = u), as (center, bandwidth,
+// linear gain).
+pub fn vowel_formant(vowel: f32, k: usize) -> (f32, f32, f32) {
+    let pos = vowel.clamp(0.0, (VOWELS.len() - 1) as f32);
+    let idx = (pos as usize).min(VOWELS.len() - 2);
+    let frac = pos - (idx as f32);
+    let (fa, ba, ga) = VOWELS[idx][k];
+    let (fb, bb, gb) = VOWELS[idx + 1][k];
+    (
+        fa + (fb - fa) * frac,
+        ba + (bb - ba) * frac,
+        10f32.powf((ga + (gb - ga) * frac) / 20.0),
+    )
+}
+
+// A parallel bank of bandpass resonators tuned to the formants of `vowel`, with every center
+// frequency scaled by `shift`.
+#[derive(Debug)]
+pub struct Formant {
+    pub input: GenBox,
+    pub vowel: GenBox,
+    pub shift: GenBox,
+    pub coeffs: [BiquadCoeffs; FORMANTS],
+    pub gains: [f32; FORMANTS],
+    pub states: [BiquadState; FORMANTS],
+    pub last: (f32, f32),
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Formant {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let vowel = self.vowel.eval(params);
+        let shift = self.shift.eval(params);
+        for i in 0..self.buf.len() {
+            let ctl = (vowel.value_at(i), shift.value_at(i));
+            if ctl != self.last {
+                for k in 0..FORMANTS {
+                    let (freq, bw, gain) = vowel_formant(ctl.0, k);
+                    let freq = freq * ctl.1.max(0.0);
+                    self.coeffs[k] = BiquadCoeffs::new(
+                        BiquadMode::BandPass,
+                        freq,
+                        freq / bw,
+                        0.0,
+                        params.env.sample_rate,
+                    );
+                    self.gains[k] = gain;
+                }
+                self.last = ctl;
+            }
+
+            let x = input.value_at(i);
+            let mut acc = 0.0;
+            for k in 0..FORMANTS {
+                acc += self.gains[k] * self.states[k].process(&self.coeffs[k], x);
+            }
+            self.buf[i] = acc;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct FormantFactory;
+
+impl GeneratorFactory for FormantFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let coeffs = BiquadCoeffs::new(
+            BiquadMode::BandPass,
+            1000.0,
+            1.0,
+            0.0,
+            params.env.sample_rate,
+        );
+        Ok(Box::new(Formant {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            vowel: params
+                .remove_param("vowel", 1)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            shift: params
+                .remove_param("shift", 2)
+                .unwrap_or(ParamValue::Float(1.0))
+                .into_gen()?,
+            coeffs: [coeffs; FORMANTS],
+            gains: [0.0; FORMANTS],
+            states: Default::default(),
+            last: (f32::NAN, f32::NAN),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryFormant: FormantFactory = FormantFactory;
+
+#[derive(Debug, Clone, Copy)]
+pub struct VocoderBand {
+    pub coeffs: BiquadCoeffs,
+    pub modulator: BiquadState,
+    pub carrier: BiquadState,
+    pub follower: FollowerState,
+}
+
+// Splits both inputs into `bands` bandpass bands spaced logarithmically from `low` to `high` Hz,
+// and scales each carrier band by the envelope of the matching modulator band.
+#[derive(Debug)]
+pub struct Vocoder {
+    pub carrier: GenBox,
+    pub modulator: GenBox,
+    pub attack: GenBox,
+    pub release: GenBox,
+    pub bands: Vec<VocoderBand>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Vocoder {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let sr = params.env.sample_rate;
+        let carrier = self.carrier.eval(params);
+        let modulator = self.modulator.eval(params);
+        let attack = time_coef(self.attack.eval(params).first(), sr);
+        let release = time_coef(self.release.eval(params).first(), sr);
+        for i in 0..self.buf.len() {
+            let (c, m) = (carrier.value_at(i), modulator.value_at(i));
+            let mut acc = 0.0;
+            for band in self.bands.iter_mut() {
+                let level = band.modulator.process(&band.coeffs, m);
+                let env = band.follower.process(level, attack, release);
+                acc += env * band.carrier.process(&band.coeffs, c);
+            }
+            self.buf[i] = acc;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct VocoderFactory;
+
+impl GeneratorFactory for VocoderFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let count = params
+            .get_param("bands", 2, &mut ParamValue::Integer(16))
+            .as_isize()?
+            .max(1) as usize;
+        let low = params
+            .get_param("low", 3, &mut ParamValue::Float(100.0))
+            .as_f32()?
+            .max(1.0);
+        let high = params
+            .get_param("high", 4, &mut ParamValue::Float(8000.0))
+            .as_f32()?
+            .max(low);
+        let q = params
+            .get_param("q", 5, &mut ParamValue::Float(6.0))
+            .as_f32()?;
+
+        let bands = (0..count)
+            .map(|k| {
+                let t = if count > 1 {
+                    (k as f32) / ((count - 1) as f32)
+                } else {
+                    0.5
+                };
+                let freq = low * (high / low).powf(t);
+                VocoderBand {
+                    coeffs: BiquadCoeffs::new(
+                        BiquadMode::BandPass,
+                        freq,
+                        q,
+                        0.0,
+                        params.env.sample_rate,
+                    ),
+                    modulator: Default::default(),
+                    carrier: Default::default(),
+                    follower: Default::default(),
+                }
+            })
+            .collect();
+
+        Ok(Box::new(Vocoder {
+            carrier: params.remove_param("carrier", 0)?.into_gen()?,
+            modulator: params.remove_param("modulator", 1)?.into_gen()?,
+            attack: params
+                .remove_param("attack", 6)
+                .unwrap_or(ParamValue::Float(0.005))
+                .into_gen()?,
+            release: params
+                .remove_param("release", 7)
+                .unwrap_or(ParamValue::Float(0.05))
+                .into_gen()?,
+            bands,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryVocoder: VocoderFactory = VocoderFactory;
@@ -0,0 +1,170 @@
+use super::lut::render_table;
+use super::noise::seeded_rng;
+use super::sample::load_wav;
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+use ::rand::{Rng, XorShiftRng};
+
+const MAX_GRAINS: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Grain {
+    pub pos: f32,
+    pub rate: f32,
+    pub age: f32,
+    pub len: f32,
+}
+
+// Spawns Hann-windowed grains `density` times a second, each `size` seconds long, reading the
+// source from `position` (0 to 1 across it) at `pitch` times the original speed. `jitter` (0 to
+// 1) randomizes both each grain's start position and the time until the next grain. Table
+// sources wrap around; sample sources are silent past either end.
+#[derive(Debug)]
+pub struct Granular {
+    pub data: Vec<Sample>,
+    pub wrap: bool,
+    pub density: GenBox,
+    pub size: GenBox,
+    pub position: GenBox,
+    pub pitch: GenBox,
+    pub jitter: GenBox,
+    pub grains: Vec<Grain>,
+    pub countdown: f32,
+    pub rng: XorShiftRng,
+    pub buf: SampleBuffer,
+}
+
+fn read_source(data: &[Sample], wrap: bool, pos: f32) -> Sample {
+    let len = data.len();
+    let pos = if wrap {
+        pos.rem_euclid(len as f32)
+    } else if pos < 0.0 || pos >= (len - 1) as f32 {
+        return 0.0;
+    } else {
+        pos
+    };
+    let idx = (pos as usize).min(len - 1);
+    let frac = pos - (idx as f32);
+    let a = data[idx];
+    a + (data[(idx + 1) % len] - a) * frac
+}
+
+impl Generator for Granular {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let sr = params.env.sample_rate;
+        let density = self.density.eval(params);
+        let size = self.size.eval(params);
+        let position = self.position.eval(params);
+        let pitch = self.pitch.eval(params);
+        let jitter = self.jitter.eval(params);
+        for i in 0..self.buf.len() {
+            let density = density.value_at(i).max(0.0);
+            let len = (size.value_at(i) * sr).max(1.0);
+            let jitter = jitter.value_at(i).clamp(0.0, 1.0);
+
+            self.countdown = (self.countdown - 1.0).max(0.0);
+            if self.countdown <= 0.0 && density > 0.0 {
+                let interval = sr / density;
+                let spread = 2.0 * self.rng.next_f32() - 1.0;
+                self.countdown += (interval * (1.0 + jitter * spread)).max(1.0);
+                if self.grains.len() < MAX_GRAINS {
+                    let offset = jitter * (2.0 * self.rng.next_f32() - 1.0);
+                    let start = (position.value_at(i) + offset) * (self.data.len() as f32);
+                    self.grains.push(Grain {
+                        pos: start,
+                        rate: pitch.value_at(i),
+                        age: 0.0,
+                        len,
+                    });
+                }
+            }
+
+            let mut acc = 0.0;
+            for grain in self.grains.iter() {
+                let window = 0.5 - 0.5 * (2.0 * PI * grain.age / grain.len).cos();
+                acc += window * read_source(&self.data, self.wrap, grain.pos);
+            }
+            for grain in self.grains.iter_mut() {
+                grain.pos += grain.rate;
+                grain.age += 1.0;
+            }
+            self.grains.retain(|g| g.age < g.len);
+
+            // Scale by the expected overlap so density and size don't change the loudness much.
+            self.buf[i] = acc / (density * len / sr).max(1.0).sqrt();
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct GranularFactory;
+
+impl GeneratorFactory for GranularFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        // A string source is a WAV file; anything else is rendered to a table like lutgen.
+        let samps = params
+            .vars
+            .remove("samples")
+            .unwrap_or(ParamValue::Integer(256))
+            .as_isize()?;
+        let var = params
+            .vars
+            .remove("var")
+            .unwrap_or(ParamValue::String("lut_freq".to_string()))
+            .as_string()?;
+        let (mut data, wrap) = match params.remove_param("source", 0)? {
+            ParamValue::String(path) => (load_wav(&path, params.env.sample_rate)?.samples, false),
+            pv => (
+                render_table(pv.into_gen()?, samps.max(1) as usize, var, &params.env),
+                true,
+            ),
+        };
+        if data.len() < 2 {
+            data.resize(2, 0.0);
+        }
+
+        Ok(Box::new(Granular {
+            data,
+            wrap,
+            density: params
+                .remove_param("density", 1)
+                .unwrap_or(ParamValue::Float(20.0))
+                .into_gen()?,
+            size: params
+                .remove_param("size", 2)
+                .unwrap_or(ParamValue::Float(0.05))
+                .into_gen()?,
+            position: params
+                .remove_param("position", 3)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            pitch: params
+                .remove_param("pitch", 4)
+                .unwrap_or(ParamValue::Float(1.0))
+                .into_gen()?,
+            jitter: params
+                .remove_param("jitter", 5)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            grains: Vec::with_capacity(MAX_GRAINS),
+            countdown: 0.0,
+            rng: seeded_rng(None),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: GranularFactory = GranularFactory;
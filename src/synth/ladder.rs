@@ -0,0 +1,73 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+// Four trapezoidal one-pole stages in series, with the global resonance feedback taken through a
+// unit delay and a tanh saturator, after the transistor ladder. res of 1.0 is the edge of
+// self-oscillation.
+#[derive(Debug)]
+pub struct Ladder {
+    pub input: GenBox,
+    pub cutoff: GenBox,
+    pub res: GenBox,
+    pub stages: [f32; 4],
+    pub last: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Ladder {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let cutoff = self.cutoff.eval(params);
+        let res = self.res.eval(params);
+        let nyquist = 0.49 * params.env.sample_rate;
+        for i in 0..self.buf.len() {
+            let g = (PI * cutoff.value_at(i).max(1.0).min(nyquist) / params.env.sample_rate).tan();
+            let gg = g / (1.0 + g);
+            let k = 4.0 * res.value_at(i).clamp(0.0, 1.0);
+
+            let mut u = (input.value_at(i) - k * self.last).tanh();
+            for s in self.stages.iter_mut() {
+                let v = (u - *s) * gg;
+                let y = v + *s;
+                *s = y + v;
+                u = y;
+            }
+
+            self.last = u;
+            self.buf[i] = u;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct LadderFactory;
+
+impl GeneratorFactory for LadderFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Ladder {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            cutoff: params.remove_param("cutoff", 1)?.into_gen()?,
+            res: params
+                .remove_param("res", 2)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            stages: [0.0; 4],
+            last: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: LadderFactory = LadderFactory;
@@ -1,13 +1,234 @@
 use super::{
-    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
-    Parameters, Rate, Sample, SampleBuffer,
+    mem, Environment, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory,
+    ParamValue, Parameters, Rate, Sample, SampleBuffer,
 };
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interp {
+    None,
+    Linear,
+    Cubic,
+}
+
+impl Interp {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            Interp::None => "none",
+            Interp::Linear => "linear",
+            Interp::Cubic => "cubic",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Interp {
+    fn from(s: &'a str) -> Interp {
+        match s {
+            "none" => Interp::None,
+            "cubic" => Interp::Cubic,
+            _ => Interp::Linear,
+        }
+    }
+}
+
+type Complex = (f32, f32);
+
+fn cmul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+// In-place iterative radix-2 FFT; `data.len()` must be a power of two.
+fn fft_pow2(data: &mut [Complex]) {
+    let n = data.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= n {
+        let twiddle: Vec<Complex> = (0..size / 2)
+            .map(|k| {
+                let (sin, cos) = (-2.0 * PI * (k as f32) / (size as f32)).sin_cos();
+                (cos, sin)
+            })
+            .collect();
+        for start in (0..n).step_by(size) {
+            for (k, &w) in twiddle.iter().enumerate() {
+                let a = data[start + k];
+                let b = cmul(data[start + k + size / 2], w);
+                data[start + k] = (a.0 + b.0, a.1 + b.1);
+                data[start + k + size / 2] = (a.0 - b.0, a.1 - b.1);
+            }
+        }
+        size *= 2;
+    }
+}
+
+// Forward DFT of any length in O(n log n): radix-2 directly, otherwise Bluestein's chirp-z
+// transform over a padded power-of-two convolution.
+fn dft(data: &[Complex]) -> Vec<Complex> {
+    let n = data.len();
+    if n.is_power_of_two() {
+        let mut out = data.to_vec();
+        fft_pow2(&mut out);
+        return out;
+    }
+
+    // k^2 is reduced mod 2n in integers first; the angle loses too much precision otherwise.
+    let chirp: Vec<Complex> = (0..n)
+        .map(|k| {
+            let k2 = ((k as u64) * (k as u64) % (2 * n as u64)) as f32;
+            let (sin, cos) = (-PI * k2 / (n as f32)).sin_cos();
+            (cos, sin)
+        })
+        .collect();
+    let m = (2 * n - 1).next_power_of_two();
+    let mut a = vec![(0.0, 0.0); m];
+    let mut b = vec![(0.0, 0.0); m];
+    for k in 0..n {
+        a[k] = cmul(data[k], chirp[k]);
+        b[k] = (chirp[k].0, -chirp[k].1);
+        if k > 0 {
+            b[m - k] = b[k];
+        }
+    }
+    fft_pow2(&mut a);
+    fft_pow2(&mut b);
+
+    // Inverse FFT of the product as conj(FFT(conj(x))) / m.
+    let mut conv: Vec<Complex> = a
+        .iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| {
+            let p = cmul(x, y);
+            (p.0, -p.1)
+        })
+        .collect();
+    fft_pow2(&mut conv);
+    (0..n)
+        .map(|k| {
+            let c = (conv[k].0 / (m as f32), -conv[k].1 / (m as f32));
+            cmul(c, chirp[k])
+        })
+        .collect()
+}
+
+// A single-cycle table and, if `mipmap` is set, progressively band-limited copies of it. Level 0
+// is the table as given; level k keeps only harmonics up to (len / 2) >> k, resynthesized from
+// its DFT.
+#[derive(Debug, Clone)]
+pub struct MipTable {
+    pub levels: Vec<Vec<Sample>>,
+}
+
+impl MipTable {
+    pub fn new(table: Vec<Sample>, mipmap: bool) -> MipTable {
+        let len = table.len();
+        if !mipmap || len < 4 {
+            return MipTable {
+                levels: vec![table],
+            };
+        }
+
+        let spectrum = dft(&table.iter().map(|&s| (s, 0.0)).collect::<Vec<_>>());
+        let mut levels = vec![table];
+        let mut harmonics = len / 4;
+        while harmonics >= 1 {
+            // The inverse DFT of the truncated spectrum, as conj(DFT(conj(X))) / len; only the
+            // real part is kept, since the input was real.
+            let band: Vec<Complex> = spectrum
+                .iter()
+                .enumerate()
+                .map(|(h, &(re, im))| {
+                    if h <= harmonics || h >= len - harmonics {
+                        (re, -im)
+                    } else {
+                        (0.0, 0.0)
+                    }
+                })
+                .collect();
+            levels.push(
+                dft(&band)
+                    .into_iter()
+                    .map(|(re, _)| re / (len as f32))
+                    .collect(),
+            );
+            harmonics /= 2;
+        }
+
+        MipTable { levels }
+    }
+
+    // Picks the fullest level whose highest harmonic stays below Nyquist at this phase velocity.
+    pub fn level_for(&self, pvel: f32) -> usize {
+        let limit = 0.5 / pvel.abs().max(1.0e-9);
+        let mut harmonics = self.levels[0].len() / 2;
+        let mut level = 0;
+        while (harmonics as f32) > limit && level + 1 < self.levels.len() {
+            harmonics /= 2;
+            level += 1;
+        }
+        level
+    }
+
+    pub fn lookup(&self, level: usize, phase: f32, interp: Interp) -> Sample {
+        let table = &self.levels[level];
+        let len = table.len();
+        let pos = phase.rem_euclid(1.0) * (len as f32);
+        let idx = (pos as usize).min(len - 1);
+        let frac = pos - (idx as f32);
+        let at = |off: usize| table[(idx + off) % len];
+
+        match interp {
+            Interp::None => at(0),
+            Interp::Linear => at(0) + (at(1) - at(0)) * frac,
+            Interp::Cubic => {
+                let (y0, y1, y2, y3) = (at(len - 1), at(0), at(1), at(2));
+                let c1 = 0.5 * (y2 - y0);
+                let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+                let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+                ((c3 * frac + c2) * frac + c1) * frac + y1
+            }
+        }
+    }
+}
+
+// Renders one cycle of `gen` into `samples` samples by evaluating it at a sample rate of
+// `samples` with `var` set to 1Hz.
+pub fn render_table(
+    mut gen: GenBox,
+    samples: usize,
+    var: String,
+    env: &Environment,
+) -> Vec<Sample> {
+    let mut genparams = Parameters {
+        env: env.clone(),
+        ..Default::default()
+    };
+    genparams.env.sample_rate = samples as f32;
+    genparams.vars.insert(var, 1.0);
+
+    gen.set_buffer(SampleBuffer::new(samples));
+    gen.eval(&genparams);
+
+    gen.set_buffer(SampleBuffer::new(0)).samples
+}
 
 #[derive(Debug)]
 pub struct Lut {
     pub freq: GenBox,
     pub phase: f32,
-    pub lut: Vec<Sample>,
+    pub table: MipTable,
+    pub interp: Interp,
     pub buf: SampleBuffer,
 }
 
@@ -16,9 +237,11 @@ impl Generator for Lut {
         self.buf.rate = Rate::Sample;
 
         let pvel = self.freq.eval(params).first() / params.env.sample_rate;
+        let level = self.table.level_for(pvel);
         for i in 0..self.buf.len() {
-            self.buf[i] = self.lut
-                [(((self.phase + pvel * (i as f32)) % 1.0) * (self.lut.len() as f32)) as usize];
+            self.buf[i] = self
+                .table
+                .lookup(level, self.phase + pvel * (i as f32), self.interp);
         }
 
         self.phase = (self.phase + pvel * (self.buf.len() as f32)) % 1.0;
@@ -32,17 +255,37 @@ impl Generator for Lut {
     }
 }
 
+fn interp_param(params: &mut FactoryParameters) -> Result<Interp, GenFactoryError> {
+    let interp = params
+        .vars
+        .remove("interp")
+        .unwrap_or(ParamValue::String("linear".to_string()))
+        .as_string()?;
+    Ok((&*interp).into())
+}
+
+fn mipmap_param(params: &mut FactoryParameters) -> Result<bool, GenFactoryError> {
+    Ok(params
+        .vars
+        .remove("mipmap")
+        .unwrap_or(ParamValue::Integer(1))
+        .as_isize()?
+        != 0)
+}
+
 pub struct LutDataFactory;
 
 impl GeneratorFactory for LutDataFactory {
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mipmap = mipmap_param(params)?;
         Ok(Box::new(Lut {
             freq: params.remove_param("freq", 0)?.into_gen()?,
             phase: params
                 .get_param("phase", 1, &mut ParamValue::Float(0.0))
                 .as_f32()?,
+            interp: interp_param(params)?,
             buf: SampleBuffer::new(params.env.default_buffer_size),
-            lut: {
+            table: {
                 let mut lut: Vec<Sample> = Vec::new();
                 let mut i = 0;
 
@@ -58,7 +301,7 @@ impl GeneratorFactory for LutDataFactory {
                     ));
                 }
 
-                lut
+                MipTable::new(lut, mipmap)
             },
         }))
     }
@@ -71,32 +314,122 @@ pub struct LutGenFactory;
 impl GeneratorFactory for LutGenFactory {
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
         eprintln!("LutGenFactory::new({:?})", params);
+        let mipmap = mipmap_param(params)?;
         Ok(Box::new(Lut {
             freq: params.remove_param("freq", 2)?.into_gen()?,
             phase: params
                 .get_param("phase", 3, &mut ParamValue::Float(0.0))
                 .as_f32()?,
+            interp: interp_param(params)?,
             buf: SampleBuffer::new(params.env.default_buffer_size),
-            lut: {
-                let mut gen = params.remove_param("gen", 0)?.into_gen()?;
+            table: {
+                let gen = params.remove_param("gen", 0)?.into_gen()?;
                 let samps = params.get_req_param("samples", 1)?.as_f32()?;
                 let var = params
                     .get_param("var", 4, &mut ParamValue::String("lut_freq".to_string()))
                     .as_string()?;
-                let mut genparams = Parameters {
-                    env: params.env.clone(),
-                    ..Default::default()
-                };
-                genparams.env.sample_rate = samps;
-                genparams.vars.insert(var, 1.0);
 
-                gen.set_buffer(SampleBuffer::new(samps as usize));
-                gen.eval(&genparams);
-
-                gen.set_buffer(SampleBuffer::new(0)).samples
+                MipTable::new(render_table(gen, samps as usize, var, &params.env), mipmap)
             },
         }))
     }
 }
 
 pub static FactoryLutGen: LutGenFactory = LutGenFactory;
+
+// Morphs between several tables: `position` sweeps 0..1 across them, crossfading neighbours.
+#[derive(Debug)]
+pub struct Wavetable {
+    pub freq: GenBox,
+    pub position: GenBox,
+    pub phase: f32,
+    pub tables: Vec<MipTable>,
+    pub interp: Interp,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Wavetable {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let pvel = self.freq.eval(params).first() / params.env.sample_rate;
+        let position = self.position.eval(params);
+        let levels: Vec<usize> = self.tables.iter().map(|t| t.level_for(pvel)).collect();
+        let last = self.tables.len() - 1;
+        for i in 0..self.buf.len() {
+            let phase = self.phase + pvel * (i as f32);
+            let pos = position.value_at(i).clamp(0.0, 1.0) * (last as f32);
+            let idx = (pos as usize).min(last);
+            let frac = pos - (idx as f32);
+            let a = self.tables[idx].lookup(levels[idx], phase, self.interp);
+            self.buf[i] = if idx < last && frac > 0.0 {
+                let b = self.tables[idx + 1].lookup(levels[idx + 1], phase, self.interp);
+                a + (b - a) * frac
+            } else {
+                a
+            };
+        }
+
+        self.phase = (self.phase + pvel * (self.buf.len() as f32)) % 1.0;
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct WavetableFactory;
+
+impl GeneratorFactory for WavetableFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        // Tables are positional from 2 onward, so these are only accepted by name.
+        let samps = params
+            .vars
+            .remove("samples")
+            .unwrap_or(ParamValue::Integer(256))
+            .as_isize()?;
+        let var = params
+            .vars
+            .remove("var")
+            .unwrap_or(ParamValue::String("lut_freq".to_string()))
+            .as_string()?;
+        let mipmap = mipmap_param(params)?;
+
+        let mut tables = Vec::new();
+        while let Ok(gen) = params.remove_param("_", 2 + tables.len()) {
+            tables.push(MipTable::new(
+                render_table(
+                    gen.into_gen()?,
+                    samps.max(1) as usize,
+                    var.clone(),
+                    &params.env,
+                ),
+                mipmap,
+            ));
+        }
+
+        if tables.is_empty() {
+            return Err(GenFactoryError::MissingRequiredParam(
+                "tables".to_string(),
+                2,
+            ));
+        }
+
+        Ok(Box::new(Wavetable {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            position: params
+                .remove_param("position", 1)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            phase: 0.0,
+            tables,
+            interp: interp_param(params)?,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryWavetable: WavetableFactory = WavetableFactory;
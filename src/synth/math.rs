@@ -2,7 +2,9 @@ use super::{
     FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, Parameters, Rate,
     SampleBuffer,
 };
-use std::mem;
+use std::{cmp, mem};
+
+use crate::types::Pitch;
 
 #[derive(Debug)]
 pub struct Add {
@@ -179,3 +181,221 @@ impl GeneratorFactory for ReciprocateFactory {
 }
 
 pub static FactoryReciprocate: ReciprocateFactory = ReciprocateFactory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Abs,
+    Exp,
+    Exp2,
+    Log,
+    Log2,
+    Floor,
+    Ceil,
+    Round,
+    Sqrt,
+    Mtof,
+    Ftom,
+}
+
+impl UnaryOp {
+    pub fn apply(&self, x: f32) -> f32 {
+        match *self {
+            UnaryOp::Abs => x.abs(),
+            UnaryOp::Exp => x.exp(),
+            UnaryOp::Exp2 => x.exp2(),
+            UnaryOp::Log => x.ln(),
+            UnaryOp::Log2 => x.log2(),
+            UnaryOp::Floor => x.floor(),
+            UnaryOp::Ceil => x.ceil(),
+            UnaryOp::Round => x.round(),
+            UnaryOp::Sqrt => x.sqrt(),
+            UnaryOp::Mtof => Pitch::MIDI(x).to_freq(),
+            UnaryOp::Ftom => Pitch::Freq(x).to_midi(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Unary {
+    pub value: GenBox,
+    pub op: UnaryOp,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Unary {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.update_from(self.value.eval(params));
+        match self.buf.rate {
+            Rate::Sample => {
+                for v in self.buf.iter_mut() {
+                    *v = self.op.apply(*v);
+                }
+            }
+            Rate::Control => {
+                self.buf[0] = self.op.apply(self.buf[0]);
+            }
+        }
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct UnaryFactory(pub UnaryOp);
+
+impl GeneratorFactory for UnaryFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let gen = params.remove_param("value", 0)?.into_gen()?;
+        let len = gen.buffer().len();
+        Ok(Box::new(Unary {
+            value: gen,
+            op: self.0,
+            buf: SampleBuffer::new(len),
+        }))
+    }
+}
+
+pub static FactoryAbs: UnaryFactory = UnaryFactory(UnaryOp::Abs);
+pub static FactoryExp: UnaryFactory = UnaryFactory(UnaryOp::Exp);
+pub static FactoryExp2: UnaryFactory = UnaryFactory(UnaryOp::Exp2);
+pub static FactoryLog: UnaryFactory = UnaryFactory(UnaryOp::Log);
+pub static FactoryLog2: UnaryFactory = UnaryFactory(UnaryOp::Log2);
+pub static FactoryFloor: UnaryFactory = UnaryFactory(UnaryOp::Floor);
+pub static FactoryCeil: UnaryFactory = UnaryFactory(UnaryOp::Ceil);
+pub static FactoryRound: UnaryFactory = UnaryFactory(UnaryOp::Round);
+pub static FactorySqrt: UnaryFactory = UnaryFactory(UnaryOp::Sqrt);
+pub static FactoryMtof: UnaryFactory = UnaryFactory(UnaryOp::Mtof);
+pub static FactoryFtom: UnaryFactory = UnaryFactory(UnaryOp::Ftom);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Min,
+    Max,
+    Pow,
+    Mod,
+}
+
+impl BinaryOp {
+    pub fn apply(&self, a: f32, b: f32) -> f32 {
+        match *self {
+            BinaryOp::Min => a.min(b),
+            BinaryOp::Max => a.max(b),
+            BinaryOp::Pow => a.powf(b),
+            // Euclidean, so phase-like values wrap into [0, b) even when negative.
+            BinaryOp::Mod => a.rem_euclid(b),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Binary {
+    pub left: GenBox,
+    pub right: GenBox,
+    pub op: BinaryOp,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Binary {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let left = self.left.eval(params);
+        let right = self.right.eval(params);
+
+        if left.rate == Rate::Control && right.rate == Rate::Control {
+            self.buf.set(self.op.apply(left.first(), right.first()));
+            return &self.buf;
+        }
+
+        self.buf.rate = Rate::Sample;
+        for i in 0..self.buf.len() {
+            self.buf[i] = self.op.apply(left.value_at(i), right.value_at(i));
+        }
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct BinaryFactory(pub BinaryOp);
+
+impl GeneratorFactory for BinaryFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let left = params.remove_param("left", 0)?.into_gen()?;
+        let right = params.remove_param("right", 1)?.into_gen()?;
+        let buf = SampleBuffer::new(cmp::max(left.buffer().len(), right.buffer().len()));
+        Ok(Box::new(Binary {
+            left,
+            right,
+            op: self.0,
+            buf,
+        }))
+    }
+}
+
+pub static FactoryMin: BinaryFactory = BinaryFactory(BinaryOp::Min);
+pub static FactoryMax: BinaryFactory = BinaryFactory(BinaryOp::Max);
+pub static FactoryPow: BinaryFactory = BinaryFactory(BinaryOp::Pow);
+pub static FactoryMod: BinaryFactory = BinaryFactory(BinaryOp::Mod);
+
+#[derive(Debug)]
+pub struct Clamp {
+    pub value: GenBox,
+    pub low: GenBox,
+    pub high: GenBox,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Clamp {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let value = self.value.eval(params);
+        let low = self.low.eval(params);
+        let high = self.high.eval(params);
+
+        if value.rate == Rate::Control && low.rate == Rate::Control && high.rate == Rate::Control {
+            self.buf
+                .set(value.first().max(low.first()).min(high.first()));
+            return &self.buf;
+        }
+
+        self.buf.rate = Rate::Sample;
+        for i in 0..self.buf.len() {
+            self.buf[i] = value.value_at(i).max(low.value_at(i)).min(high.value_at(i));
+        }
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct ClampFactory;
+
+impl GeneratorFactory for ClampFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let value = params.remove_param("value", 0)?.into_gen()?;
+        let low = params.remove_param("low", 1)?.into_gen()?;
+        let high = params.remove_param("high", 2)?.into_gen()?;
+        let buf = SampleBuffer::new(cmp::max(
+            value.buffer().len(),
+            cmp::max(low.buffer().len(), high.buffer().len()),
+        ));
+        Ok(Box::new(Clamp {
+            value,
+            low,
+            high,
+            buf,
+        }))
+    }
+}
+
+pub static FactoryClamp: ClampFactory = ClampFactory;
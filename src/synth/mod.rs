@@ -25,6 +25,7 @@ pub struct SampleBuffer {
 pub struct Environment {
     pub sample_rate: f32,
     pub default_buffer_size: usize,
+    pub feedback: FeedbackBus,
 }
 
 impl Default for Environment {
@@ -32,6 +33,7 @@ impl Default for Environment {
         Environment {
             sample_rate: 44100.0,
             default_buffer_size: 64,
+            feedback: Default::default(),
         }
     }
 }
@@ -76,6 +78,14 @@ impl SampleBuffer {
         *self.samples.first().unwrap()
     }
 
+    // NB: Sample-rate buffers shorter than the caller's repeat their last sample.
+    pub fn value_at(&self, idx: usize) -> Sample {
+        match self.rate {
+            Rate::Sample => self.samples[cmp::min(idx, self.len() - 1)],
+            Rate::Control => self.samples[0],
+        }
+    }
+
     pub fn set(&mut self, val: Sample) {
         self.samples[0] = val;
         self.rate = Rate::Control;
@@ -189,6 +199,7 @@ pub enum GenFactoryError {
     MissingRequiredParam(String, usize),
     CannotConvert(ParamKind, ParamKind),
     BadType(ParamKind),
+    BadFile(String, String),
 }
 
 #[derive(Debug)]
@@ -212,6 +223,9 @@ impl GenFactoryErrorType {
                 format!("Cannot convert {:?} to {:?}", from, to)
             }
             GenFactoryError::BadType(ty) => format!("Bad parameter type {:?}", ty),
+            GenFactoryError::BadFile(ref path, ref why) => {
+                format!("Cannot load file {}: {}", path, why)
+            }
         };
 
         ret
@@ -340,6 +354,8 @@ impl<'a> From<&'a ParamValue> for ParamKind {
 pub struct FactoryParameters {
     pub env: Environment,
     pub vars: HashMap<String, ParamValue>,
+    // Further, independent builds of the same parameters, if the factory asked for copies.
+    pub copies: Vec<FactoryParameters>,
 }
 
 impl FactoryParameters {
@@ -415,12 +431,25 @@ pub trait GeneratorFactory {
     // would compromise object safety; for the same reason, the return of this may only be a
     // Box<Generator>, which necessitates allocation.
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError>;
+
+    // Whether the generators passed as parameters need a buffer size of 1, because this factory's
+    // generator evaluates them a sample at a time. If so, the parser builds them again that way
+    // before calling new.
+    fn per_sample(&self, _params: &mut FactoryParameters) -> bool {
+        false
+    }
+
+    // How many independent builds of the parameters this factory needs, for generators that run
+    // several copies of a subtree. The parser puts the extra builds in `copies`.
+    fn copies(&self, _params: &mut FactoryParameters) -> usize {
+        1
+    }
 }
 
 pub mod param;
 pub use self::param::Param;
 pub mod math;
-pub use self::math::{Add, Mul, Negate, Reciprocate};
+pub use self::math::{Add, Binary, BinaryOp, Clamp, Mul, Negate, Reciprocate, Unary, UnaryOp};
 pub mod rel;
 pub use self::rel::{Rel, RelOp};
 pub mod logic;
@@ -428,19 +457,63 @@ pub use self::logic::IfElse;
 pub mod util;
 pub use self::util::{ControlRate, SampleRate};
 pub mod lut;
-pub use self::lut::Lut;
+pub use self::lut::{Lut, MipTable, Wavetable};
 pub mod sine;
 pub use self::sine::Sine;
+pub mod pm;
+pub use self::pm::Pm;
 pub mod saw;
 pub use self::saw::Saw;
 pub mod triangle;
 pub use self::triangle::Triangle;
 pub mod square;
 pub use self::square::Square;
+pub mod blosc;
+pub use self::blosc::{BlSaw, BlSquare, BlTriangle, OscShape};
+pub mod sync;
+pub use self::sync::HardSync;
+pub mod unison;
+pub use self::unison::Unison;
+pub mod additive;
+pub use self::additive::Additive;
 pub mod noise;
-pub use self::noise::Noise;
+pub use self::noise::{Noise, NoiseColor, SampleHold};
 pub mod adsr;
 pub use self::adsr::DAHDSR;
+pub mod env;
+pub use self::env::{Breakpoint, Envelope, EnvelopeState};
+pub mod biquad;
+pub use self::biquad::{Biquad, BiquadMode};
+pub mod svf;
+pub use self::svf::{Svf, SvfMode};
+pub mod ladder;
+pub use self::ladder::Ladder;
+pub mod formant;
+pub use self::formant::{Formant, Vocoder};
+pub mod delay;
+pub use self::delay::{Delay, DelayLine};
+pub mod reverb;
+pub use self::reverb::Reverb;
+pub mod sample;
+pub use self::sample::Sampler;
+pub mod granular;
+pub use self::granular::Granular;
+pub mod pluck;
+pub use self::pluck::Pluck;
+pub mod modal;
+pub use self::modal::{Modal, Mode};
+pub mod shape;
+pub use self::shape::{Downsample, ShapeOp, Shaper};
+pub mod slew;
+pub use self::slew::{Lag, Slew};
+pub mod dynamics;
+pub use self::dynamics::{Compressor, Follower, Limit, Limiter};
+pub mod feedback;
+pub use self::feedback::{FeedbackBus, FeedbackRead, FeedbackWrite};
+pub mod seq;
+pub use self::seq::{Clock, Counter, Steps};
+pub mod scale;
+pub use self::scale::{Quantize, Scale};
 
 pub fn all_factories() -> HashMap<String, &'static dyn GeneratorFactory> {
     let mut ret = HashMap::new();
@@ -465,6 +538,70 @@ pub fn all_factories() -> HashMap<String, &'static dyn GeneratorFactory> {
         "reciprocate".to_string(),
         &self::math::FactoryReciprocate as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "abs".to_string(),
+        &self::math::FactoryAbs as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "exp".to_string(),
+        &self::math::FactoryExp as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "exp2".to_string(),
+        &self::math::FactoryExp2 as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "log".to_string(),
+        &self::math::FactoryLog as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "log2".to_string(),
+        &self::math::FactoryLog2 as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "floor".to_string(),
+        &self::math::FactoryFloor as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "ceil".to_string(),
+        &self::math::FactoryCeil as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "round".to_string(),
+        &self::math::FactoryRound as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "sqrt".to_string(),
+        &self::math::FactorySqrt as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "mtof".to_string(),
+        &self::math::FactoryMtof as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "ftom".to_string(),
+        &self::math::FactoryFtom as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "min".to_string(),
+        &self::math::FactoryMin as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "max".to_string(),
+        &self::math::FactoryMax as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "pow".to_string(),
+        &self::math::FactoryPow as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "mod".to_string(),
+        &self::math::FactoryMod as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "clamp".to_string(),
+        &self::math::FactoryClamp as &dyn GeneratorFactory,
+    );
     ret.insert(
         "rel".to_string(),
         &self::rel::Factory as &dyn GeneratorFactory,
@@ -489,10 +626,18 @@ pub fn all_factories() -> HashMap<String, &'static dyn GeneratorFactory> {
         "lutgen".to_string(),
         &self::lut::FactoryLutGen as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "wavetable".to_string(),
+        &self::lut::FactoryWavetable as &dyn GeneratorFactory,
+    );
     ret.insert(
         "sine".to_string(),
         &self::sine::Factory as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "pm".to_string(),
+        &self::pm::Factory as &dyn GeneratorFactory,
+    );
     ret.insert(
         "saw".to_string(),
         &self::saw::Factory as &dyn GeneratorFactory,
@@ -505,14 +650,158 @@ pub fn all_factories() -> HashMap<String, &'static dyn GeneratorFactory> {
         "square".to_string(),
         &self::square::Factory as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "blsaw".to_string(),
+        &self::blosc::FactoryBlSaw as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "blsquare".to_string(),
+        &self::blosc::FactoryBlSquare as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "bltri".to_string(),
+        &self::blosc::FactoryBlTriangle as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "sync".to_string(),
+        &self::sync::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "unison".to_string(),
+        &self::unison::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "additive".to_string(),
+        &self::additive::Factory as &dyn GeneratorFactory,
+    );
     ret.insert(
         "noise".to_string(),
         &self::noise::Factory as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "sample_hold".to_string(),
+        &self::noise::FactorySampleHold as &dyn GeneratorFactory,
+    );
     ret.insert(
         "dahdsr".to_string(),
         &self::adsr::Factory as &dyn GeneratorFactory,
     );
+    ret.insert(
+        "env".to_string(),
+        &self::env::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "biquad".to_string(),
+        &self::biquad::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "svf".to_string(),
+        &self::svf::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "ladder".to_string(),
+        &self::ladder::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "formant".to_string(),
+        &self::formant::FactoryFormant as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "vocoder".to_string(),
+        &self::formant::FactoryVocoder as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "delay".to_string(),
+        &self::delay::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "reverb".to_string(),
+        &self::reverb::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "sample".to_string(),
+        &self::sample::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "granular".to_string(),
+        &self::granular::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "pluck".to_string(),
+        &self::pluck::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "modal".to_string(),
+        &self::modal::Factory as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "saturate".to_string(),
+        &self::shape::FactorySaturate as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "clip".to_string(),
+        &self::shape::FactoryClip as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "fold".to_string(),
+        &self::shape::FactoryFold as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "crush".to_string(),
+        &self::shape::FactoryCrush as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "downsample".to_string(),
+        &self::shape::FactoryDownsample as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "slew".to_string(),
+        &self::slew::FactorySlew as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "lag".to_string(),
+        &self::slew::FactoryLag as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "glide".to_string(),
+        &self::slew::FactoryGlide as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "follower".to_string(),
+        &self::dynamics::FactoryFollower as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "compressor".to_string(),
+        &self::dynamics::FactoryCompressor as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "limiter".to_string(),
+        &self::dynamics::FactoryLimit as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "fbwrite".to_string(),
+        &self::feedback::FactoryFeedbackWrite as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "fbread".to_string(),
+        &self::feedback::FactoryFeedbackRead as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "clock".to_string(),
+        &self::seq::FactoryClock as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "counter".to_string(),
+        &self::seq::FactoryCounter as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "steps".to_string(),
+        &self::seq::FactorySteps as &dyn GeneratorFactory,
+    );
+    ret.insert(
+        "quantize".to_string(),
+        &self::scale::Factory as &dyn GeneratorFactory,
+    );
 
     ret
 }
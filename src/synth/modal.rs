@@ -0,0 +1,147 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+// A two-pole resonator at `ratio` times the base frequency, falling by 60dB in `decay` seconds.
+// The input gain is chosen so a unit impulse rings at amplitude `gain`.
+#[derive(Debug, Clone, Copy)]
+pub struct Mode {
+    pub ratio: f32,
+    pub decay: f32,
+    pub gain: f32,
+    pub a1: f32,
+    pub a2: f32,
+    pub b0: f32,
+    pub y1: f32,
+    pub y2: f32,
+}
+
+impl Mode {
+    pub fn tune(&mut self, freq: f32, sample_rate: f32) {
+        let w = 2.0 * PI * freq * self.ratio / sample_rate;
+        if !w.is_finite() || w <= 0.0 || w >= PI {
+            // At or above Nyquist (or at DC, or NaN): keep the mode silent.
+            self.a1 = 0.0;
+            self.a2 = 0.0;
+            self.b0 = 0.0;
+            return;
+        }
+        let r = 10f32.powf(-3.0 / (self.decay.max(1.0e-3) * sample_rate));
+        self.a1 = 2.0 * r * w.cos();
+        self.a2 = -r * r;
+        self.b0 = w.sin() * self.gain;
+    }
+
+    pub fn process(&mut self, x: Sample) -> Sample {
+        let y = self.a1 * self.y1 + self.a2 * self.y2 + self.b0 * x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+// A bank of modes struck by a unit impulse on each rising edge of `gate` (on at >= 0.5, as for
+// dahdsr), plus the optional `input` signal as continuous excitation.
+#[derive(Debug)]
+pub struct Modal {
+    pub freq: GenBox,
+    pub gate: GenBox,
+    pub input: Option<GenBox>,
+    pub modes: Vec<Mode>,
+    pub last: f32,
+    pub gated: bool,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Modal {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        let gate = self.gate.eval(params);
+        let input = self.input.as_mut().map(|g| g.eval(params));
+        for i in 0..self.buf.len() {
+            let f = freq.value_at(i);
+            if f != self.last {
+                for mode in self.modes.iter_mut() {
+                    mode.tune(f, params.env.sample_rate);
+                }
+                self.last = f;
+            }
+
+            let gated = gate.value_at(i) >= 0.5;
+            let mut x = if gated && !self.gated { 1.0 } else { 0.0 };
+            self.gated = gated;
+            if let Some(input) = input {
+                x += input.value_at(i);
+            }
+
+            let mut acc = 0.0;
+            for mode in self.modes.iter_mut() {
+                acc += mode.process(x);
+            }
+            self.buf[i] = acc;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct ModalFactory;
+
+impl GeneratorFactory for ModalFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mut modes: Vec<Mode> = Vec::new();
+        while let Ok(ratio) = params
+            .get_req_param("_", 2 + 3 * modes.len())
+            .and_then(|pv| pv.as_f32())
+        {
+            let pos = 2 + 3 * modes.len();
+            modes.push(Mode {
+                ratio,
+                decay: params.get_req_param("_", pos + 1)?.as_f32()?,
+                gain: params.get_req_param("_", pos + 2)?.as_f32()?,
+                a1: 0.0,
+                a2: 0.0,
+                b0: 0.0,
+                y1: 0.0,
+                y2: 0.0,
+            });
+        }
+
+        if modes.is_empty() {
+            return Err(GenFactoryError::MissingRequiredParam(
+                "modes".to_string(),
+                2,
+            ));
+        }
+
+        let input = match params.vars.remove("input") {
+            Some(pv) => Some(pv.into_gen()?),
+            None => None,
+        };
+
+        Ok(Box::new(Modal {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            gate: params
+                .remove_param("gate", 1)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            input,
+            modes,
+            last: f32::NAN,
+            gated: false,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: ModalFactory = ModalFactory;
@@ -1,23 +1,120 @@
 use super::{
-    FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, Parameters, Rate,
-    SampleBuffer,
+    FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
 };
 use std::mem;
 
 use ::rand::{Rng, SeedableRng, XorShiftRng};
 
+// Seeds from entropy when no seed is given; otherwise expands the seed with SplitMix64 (an
+// all-zero XorShift state would be stuck at zero forever).
+pub fn seeded_rng(seed: Option<isize>) -> XorShiftRng {
+    match seed {
+        None => XorShiftRng::from_seed(::rand::random()),
+        Some(seed) => {
+            let mut x = seed as u64;
+            let mut next = || {
+                x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = x;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^ (z >> 31)
+            };
+            let (a, b) = (next(), next());
+            XorShiftRng::from_seed([a as u32, (a >> 32) as u32 | 1, b as u32, (b >> 32) as u32])
+        }
+    }
+}
+
+fn seed_param(
+    params: &mut FactoryParameters,
+    pos: usize,
+) -> Result<Option<isize>, GenFactoryError> {
+    match params.remove_param("seed", pos) {
+        Ok(mut pv) => Ok(Some(pv.as_isize()?)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn random_value(rng: &mut XorShiftRng, bipolar: bool) -> Sample {
+    let v = rng.next_f32();
+    if bipolar {
+        2.0 * v - 1.0
+    } else {
+        v
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseColor {
+    White,
+    Pink,
+    Brown,
+}
+
+impl NoiseColor {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            NoiseColor::White => "white",
+            NoiseColor::Pink => "pink",
+            NoiseColor::Brown => "brown",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for NoiseColor {
+    fn from(s: &'a str) -> NoiseColor {
+        match s {
+            "pink" => NoiseColor::Pink,
+            "brown" | "red" => NoiseColor::Brown,
+            _ => NoiseColor::White,
+        }
+    }
+}
+
+// Output is in [0, 1) by default, matching the original white noise; `bipolar` makes it [-1, 1).
+// Pink uses Paul Kellet's filter and brown a leaky integrator, both fed with bipolar white noise.
 #[derive(Debug)]
 pub struct Noise {
     pub rng: XorShiftRng,
+    pub color: NoiseColor,
+    pub bipolar: bool,
+    pub state: [f32; 7],
     pub buf: SampleBuffer,
 }
 
+impl Noise {
+    fn next_bipolar(&mut self) -> Sample {
+        let w = 2.0 * self.rng.next_f32() - 1.0;
+        let b = &mut self.state;
+        match self.color {
+            NoiseColor::White => w,
+            NoiseColor::Pink => {
+                b[0] = 0.99886 * b[0] + w * 0.055_517_9;
+                b[1] = 0.99332 * b[1] + w * 0.075_075_9;
+                b[2] = 0.96900 * b[2] + w * 0.153_852;
+                b[3] = 0.86650 * b[3] + w * 0.310_485_6;
+                b[4] = 0.55000 * b[4] + w * 0.532_952_2;
+                b[5] = -0.7616 * b[5] - w * 0.016_898;
+                let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + w * 0.5362;
+                b[6] = w * 0.115_926;
+                (pink * 0.11).clamp(-1.0, 1.0)
+            }
+            NoiseColor::Brown => {
+                b[0] = (b[0] + 0.02 * w) / 1.02;
+                (b[0] * 3.5).clamp(-1.0, 1.0)
+            }
+        }
+    }
+}
+
 impl Generator for Noise {
     fn eval<'a>(&'a mut self, _params: &Parameters) -> &'a SampleBuffer {
         self.buf.rate = Rate::Sample;
 
         for i in 0..self.buf.len() {
-            self.buf[i] = self.rng.next_f32();
+            let v = self.next_bipolar();
+            self.buf[i] = if self.bipolar { v } else { 0.5 * (v + 1.0) };
         }
 
         &self.buf
@@ -34,11 +131,98 @@ pub struct NoiseFactory;
 
 impl GeneratorFactory for NoiseFactory {
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let color = params
+            .get_param("color", 0, &mut ParamValue::String("white".to_string()))
+            .as_string()?;
         Ok(Box::new(Noise {
-            rng: XorShiftRng::from_seed(::rand::random()),
+            rng: seeded_rng(seed_param(params, 2)?),
+            color: (&*color).into(),
+            bipolar: params
+                .get_param("bipolar", 1, &mut ParamValue::Integer(0))
+                .as_isize()?
+                != 0,
+            state: [0.0; 7],
             buf: SampleBuffer::new(params.env.default_buffer_size),
         }))
     }
 }
 
 pub static Factory: NoiseFactory = NoiseFactory;
+
+// Holds a random value, replacing it on each rising edge of `trig` and/or `rate` times a second.
+#[derive(Debug)]
+pub struct SampleHold {
+    pub trig: Option<GenBox>,
+    pub rate: GenBox,
+    pub rng: XorShiftRng,
+    pub bipolar: bool,
+    pub phase: f32,
+    pub gated: bool,
+    pub cur: Sample,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for SampleHold {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let trig = self.trig.as_mut().map(|t| t.eval(params));
+        let rate = self.rate.eval(params);
+        for i in 0..self.buf.len() {
+            let mut fire = false;
+            if let Some(trig) = trig {
+                let gated = trig.value_at(i) >= 0.5;
+                fire = gated && !self.gated;
+                self.gated = gated;
+            }
+            self.phase += rate.value_at(i) / params.env.sample_rate;
+            if self.phase >= 1.0 {
+                self.phase %= 1.0;
+                fire = true;
+            }
+            if fire {
+                self.cur = random_value(&mut self.rng, self.bipolar);
+            }
+            self.buf[i] = self.cur;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct SampleHoldFactory;
+
+impl GeneratorFactory for SampleHoldFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let trig = match params.remove_param("trig", 0) {
+            Ok(pv) => Some(pv.into_gen()?),
+            Err(_) => None,
+        };
+        let bipolar = params
+            .get_param("bipolar", 2, &mut ParamValue::Integer(0))
+            .as_isize()?
+            != 0;
+        let mut rng = seeded_rng(seed_param(params, 3)?);
+        Ok(Box::new(SampleHold {
+            trig,
+            rate: params
+                .remove_param("rate", 1)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            cur: random_value(&mut rng, bipolar),
+            rng,
+            bipolar,
+            phase: 0.0,
+            gated: false,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactorySampleHold: SampleHoldFactory = SampleHoldFactory;
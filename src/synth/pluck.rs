@@ -0,0 +1,93 @@
+use super::{
+    mem, DelayLine, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory,
+    ParamValue, Parameters, Rate, SampleBuffer,
+};
+
+use ::rand::{Rng, SeedableRng, XorShiftRng};
+
+// Lowest frequency the string can be tuned to; this sizes the delay line.
+const MIN_FREQ: f32 = 20.0;
+
+// Karplus-Strong string: a rising gate fills one period of the delay line with noise, which is
+// then recirculated through a two-point averaging lowpass (opened up by `bright`) and a loss
+// chosen so the string falls by 60dB in `decay` seconds.
+#[derive(Debug)]
+pub struct Pluck {
+    pub freq: GenBox,
+    pub gate: GenBox,
+    pub decay: GenBox,
+    pub bright: GenBox,
+    pub line: DelayLine,
+    pub rng: XorShiftRng,
+    pub gated: bool,
+    pub prev: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Pluck {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        let gate = self.gate.eval(params);
+        let decay = self.decay.eval(params);
+        let bright = self.bright.eval(params);
+        let sr = params.env.sample_rate;
+        for i in 0..self.buf.len() {
+            let period = sr / freq.value_at(i).max(MIN_FREQ);
+
+            let gated = gate.value_at(i) >= 0.5;
+            if gated && !self.gated {
+                self.line.clear();
+                for _ in 0..(period.round() as usize) {
+                    self.line.push(2.0 * self.rng.next_f32() - 1.0);
+                }
+                self.prev = 0.0;
+            }
+            self.gated = gated;
+
+            // The averaging filter delays by `smooth` samples, so tap that much earlier.
+            let smooth = 0.5 * (1.0 - bright.value_at(i).clamp(0.0, 1.0));
+            let cur = self.line.tap(period - smooth);
+            let loss = 10f32.powf(-3.0 * period / (decay.value_at(i).max(1.0e-3) * sr));
+            let out = loss * ((1.0 - smooth) * cur + smooth * self.prev);
+            self.prev = cur;
+            self.line.push(out);
+            self.buf[i] = out;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct PluckFactory;
+
+impl GeneratorFactory for PluckFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Pluck {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            gate: params.remove_param("gate", 1)?.into_gen()?,
+            decay: params
+                .remove_param("decay", 2)
+                .unwrap_or(ParamValue::Float(2.0))
+                .into_gen()?,
+            bright: params
+                .remove_param("bright", 3)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            line: DelayLine::new((params.env.sample_rate / MIN_FREQ).ceil() as usize + 2),
+            rng: XorShiftRng::from_seed(::rand::random()),
+            gated: false,
+            prev: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: PluckFactory = PluckFactory;
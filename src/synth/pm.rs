@@ -0,0 +1,67 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+const TAU: f32 = 2f32 * PI;
+
+// A phase-modulation operator: a sine whose phase is offset every sample by `modulation` (in
+// radians), plus `feedback` times the average of its own last two outputs, as in the DX7.
+#[derive(Debug)]
+pub struct Pm {
+    pub freq: GenBox,
+    pub modulation: GenBox,
+    pub feedback: GenBox,
+    pub phase: f32,
+    pub hist: [f32; 2],
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Pm {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        let modulation = self.modulation.eval(params);
+        let feedback = self.feedback.eval(params);
+        for i in 0..self.buf.len() {
+            let fb = feedback.value_at(i) * 0.5 * (self.hist[0] + self.hist[1]);
+            let out = (self.phase + modulation.value_at(i) + fb).sin();
+            self.hist = [out, self.hist[0]];
+            self.buf[i] = out;
+            self.phase = (self.phase + TAU * freq.value_at(i) / params.env.sample_rate) % TAU;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct PmFactory;
+
+impl GeneratorFactory for PmFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Pm {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            modulation: params
+                .remove_param("mod", 1)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            feedback: params
+                .remove_param("feedback", 2)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            phase: 0.0,
+            hist: [0.0; 2],
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: PmFactory = PmFactory;
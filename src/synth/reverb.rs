@@ -0,0 +1,133 @@
+use super::{
+    mem, DelayLine, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory,
+    ParamValue, Parameters, Rate, Sample, SampleBuffer,
+};
+
+// Freeverb tunings, in samples at 44.1kHz.
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+const FIXED_GAIN: f32 = 0.015;
+const WET_SCALE: f32 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct Comb {
+    pub line: DelayLine,
+    pub len: usize,
+    pub store: f32,
+}
+
+impl Comb {
+    pub fn new(len: usize) -> Comb {
+        Comb {
+            line: DelayLine::new(len),
+            len,
+            store: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, input: Sample, feedback: f32, damp: f32) -> Sample {
+        let out = self.line.tap_int(self.len);
+        self.store = out * (1.0 - damp) + self.store * damp;
+        self.line.push(input + self.store * feedback);
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Allpass {
+    pub line: DelayLine,
+    pub len: usize,
+}
+
+impl Allpass {
+    pub fn new(len: usize) -> Allpass {
+        Allpass {
+            line: DelayLine::new(len),
+            len,
+        }
+    }
+
+    pub fn process(&mut self, input: Sample) -> Sample {
+        let delayed = self.line.tap_int(self.len);
+        self.line.push(input + delayed * 0.5);
+        delayed - input
+    }
+}
+
+#[derive(Debug)]
+pub struct Reverb {
+    pub input: GenBox,
+    pub room: GenBox,
+    pub damp: GenBox,
+    pub mix: GenBox,
+    pub combs: Vec<Comb>,
+    pub allpasses: Vec<Allpass>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Reverb {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let room = self.room.eval(params);
+        let damp = self.damp.eval(params);
+        let mix = self.mix.eval(params);
+        for i in 0..self.buf.len() {
+            let dry = input.value_at(i);
+            let feedback = 0.7 + 0.28 * room.value_at(i).clamp(0.0, 1.0);
+            let damp = 0.4 * damp.value_at(i).clamp(0.0, 1.0);
+
+            let mut wet = 0.0;
+            for comb in self.combs.iter_mut() {
+                wet += comb.process(dry * FIXED_GAIN, feedback, damp);
+            }
+            for allpass in self.allpasses.iter_mut() {
+                wet = allpass.process(wet);
+            }
+
+            let mix = mix.value_at(i);
+            self.buf[i] = dry * (1.0 - mix) + wet * WET_SCALE * mix;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct ReverbFactory;
+
+impl GeneratorFactory for ReverbFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let scale = params.env.sample_rate / 44100.0;
+        let scaled = |len: usize| ((len as f32) * scale).round().max(1.0) as usize;
+        Ok(Box::new(Reverb {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            room: params
+                .remove_param("room", 1)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            damp: params
+                .remove_param("damp", 2)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            mix: params
+                .remove_param("mix", 3)
+                .unwrap_or(ParamValue::Float(0.3))
+                .into_gen()?,
+            combs: COMB_TUNING.iter().map(|&l| Comb::new(scaled(l))).collect(),
+            allpasses: ALLPASS_TUNING
+                .iter()
+                .map(|&l| Allpass::new(scaled(l)))
+                .collect(),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: ReverbFactory = ReverbFactory;
@@ -0,0 +1,258 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+use std::fs;
+
+use ::byteorder::{ByteOrder, LittleEndian};
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+// Mono sample data, already resampled to the environment's sample rate. Loop points (from a
+// `smpl` chunk, if the file had one) are in those resampled frames.
+#[derive(Debug, Clone)]
+pub struct WavData {
+    pub samples: Vec<Sample>,
+    pub loop_points: Option<(usize, usize)>,
+}
+
+fn bad_file(path: &str, why: &str) -> GenFactoryError {
+    GenFactoryError::BadFile(path.to_string(), why.to_string())
+}
+
+fn decode_frame(data: &[u8], format: u16, bits: u16) -> Option<Sample> {
+    match (format, bits) {
+        (FORMAT_PCM, 8) => Some((data[0] as f32 - 128.0) / 128.0),
+        (FORMAT_PCM, 16) => Some(LittleEndian::read_i16(data) as f32 / 32768.0),
+        (FORMAT_PCM, 24) => Some(LittleEndian::read_i24(data) as f32 / 8_388_608.0),
+        (FORMAT_PCM, 32) => Some(LittleEndian::read_i32(data) as f32 / 2_147_483_648.0),
+        (FORMAT_FLOAT, 32) => Some(LittleEndian::read_f32(data)),
+        (FORMAT_FLOAT, 64) => Some(LittleEndian::read_f64(data) as f32),
+        _ => None,
+    }
+}
+
+// Loads a RIFF WAVE file of integer or float PCM, mixing all channels down to mono.
+pub fn load_wav(path: &str, sample_rate: f32) -> Result<WavData, GenFactoryError> {
+    let bytes = fs::read(path).map_err(|e| bad_file(path, &e.to_string()))?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(bad_file(path, "not a RIFF WAVE file"));
+    }
+
+    let mut fmt: Option<(u16, usize, f32, u16)> = None;
+    let mut data: Option<&[u8]> = None;
+    let mut loop_points: Option<(usize, usize)> = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = LittleEndian::read_u32(&bytes[pos + 4..pos + 8]) as usize;
+        let body = &bytes[pos + 8..bytes.len().min(pos + 8 + len)];
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let mut format = LittleEndian::read_u16(&body[0..2]);
+                if format == FORMAT_EXTENSIBLE && body.len() >= 26 {
+                    format = LittleEndian::read_u16(&body[24..26]);
+                }
+                fmt = Some((
+                    format,
+                    LittleEndian::read_u16(&body[2..4]) as usize,
+                    LittleEndian::read_u32(&body[4..8]) as f32,
+                    LittleEndian::read_u16(&body[14..16]),
+                ));
+            }
+            b"data" => data = Some(body),
+            b"smpl" if body.len() >= 36 + 24 && LittleEndian::read_u32(&body[28..32]) > 0 => {
+                loop_points = Some((
+                    LittleEndian::read_u32(&body[44..48]) as usize,
+                    LittleEndian::read_u32(&body[48..52]) as usize + 1,
+                ));
+            }
+            _ => (),
+        }
+        // Chunks are padded to an even length.
+        pos += 8 + len + (len & 1);
+    }
+
+    let (format, channels, file_rate, bits) = fmt.ok_or_else(|| bad_file(path, "no fmt chunk"))?;
+    let data = data.ok_or_else(|| bad_file(path, "no data chunk"))?;
+    let width = (bits as usize).div_ceil(8);
+    if channels == 0 || width == 0 || file_rate <= 0.0 {
+        return Err(bad_file(path, "malformed fmt chunk"));
+    }
+
+    let mut frames: Vec<Sample> = Vec::with_capacity(data.len() / (width * channels));
+    for frame in data.chunks_exact(width * channels) {
+        let mut acc = 0.0;
+        for chan in frame.chunks_exact(width) {
+            acc += decode_frame(chan, format, bits)
+                .ok_or_else(|| bad_file(path, "unsupported sample format"))?;
+        }
+        frames.push(acc / (channels as f32));
+    }
+
+    let ratio = sample_rate / file_rate;
+    let samples = if (ratio - 1.0).abs() < 1.0e-6 || frames.len() < 2 {
+        frames
+    } else {
+        let len = ((frames.len() as f32) * ratio) as usize;
+        (0..len)
+            .map(|i| {
+                let src = (i as f32) / ratio;
+                let idx = (src as usize).min(frames.len() - 2);
+                let frac = src - (idx as f32);
+                frames[idx] + (frames[idx + 1] - frames[idx]) * frac
+            })
+            .collect()
+    };
+    let loop_points =
+        loop_points.map(|(s, e)| (((s as f32) * ratio) as usize, ((e as f32) * ratio) as usize));
+
+    Ok(WavData {
+        samples,
+        loop_points,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    OneShot,
+    Loop,
+}
+
+impl PlayMode {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            PlayMode::OneShot => "oneshot",
+            PlayMode::Loop => "loop",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for PlayMode {
+    fn from(s: &'a str) -> PlayMode {
+        if s == "loop" {
+            PlayMode::Loop
+        } else {
+            PlayMode::OneShot
+        }
+    }
+}
+
+// Plays `data` from the start on each rising edge of `gate`, transposed by freq / root. In loop
+// mode the region between the loop points repeats for as long as the gate is held, after which
+// playback continues through to the end.
+#[derive(Debug)]
+pub struct Sampler {
+    pub gate: GenBox,
+    pub freq: GenBox,
+    pub root: f32,
+    pub data: Vec<Sample>,
+    pub mode: PlayMode,
+    pub loop_start: f32,
+    pub loop_end: f32,
+    pub pos: f32,
+    pub playing: bool,
+    pub gated: bool,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Sampler {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let gate = self.gate.eval(params);
+        let freq = self.freq.eval(params);
+        let end = (self.data.len() as f32) - 1.0;
+        for i in 0..self.buf.len() {
+            let gated = gate.value_at(i) >= 0.5;
+            if gated && !self.gated {
+                self.pos = 0.0;
+                self.playing = true;
+            }
+            self.gated = gated;
+
+            if self.mode == PlayMode::Loop
+                && gated
+                && self.pos >= self.loop_end
+                && self.loop_end > self.loop_start
+            {
+                self.pos -= self.loop_end - self.loop_start;
+            }
+            if self.pos >= end {
+                self.playing = false;
+            }
+
+            self.buf[i] = if self.playing {
+                let idx = self.pos as usize;
+                let frac = self.pos - (idx as f32);
+                let a = self.data[idx];
+                a + (self.data[idx + 1] - a) * frac
+            } else {
+                0.0
+            };
+            if self.playing {
+                self.pos += (freq.value_at(i) / self.root).max(0.0);
+            }
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct SamplerFactory;
+
+impl GeneratorFactory for SamplerFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let path = params.get_req_param("file", 0)?.as_string()?;
+        let mut wav = load_wav(&path, params.env.sample_rate)?;
+        // Pad so interpolation always has a following sample.
+        wav.samples.push(0.0);
+        if wav.samples.len() < 2 {
+            wav.samples.push(0.0);
+        }
+        let (file_start, file_end) = wav.loop_points.unwrap_or((0, wav.samples.len() - 1));
+
+        let root = params
+            .get_param("root", 3, &mut ParamValue::Float(261.63))
+            .as_f32()?;
+        let mode = params
+            .get_param("mode", 4, &mut ParamValue::String("oneshot".to_string()))
+            .as_string()?;
+        let loop_start = match params.vars.remove("loopstart") {
+            Some(mut pv) => pv.as_f32()? * params.env.sample_rate,
+            None => file_start as f32,
+        };
+        let loop_end = match params.vars.remove("loopend") {
+            Some(mut pv) => pv.as_f32()? * params.env.sample_rate,
+            None => file_end as f32,
+        }
+        .min((wav.samples.len() - 1) as f32);
+
+        Ok(Box::new(Sampler {
+            gate: params.remove_param("gate", 1)?.into_gen()?,
+            freq: params
+                .remove_param("freq", 2)
+                .unwrap_or(ParamValue::Float(root))
+                .into_gen()?,
+            root: root.max(1.0e-3),
+            data: wav.samples,
+            mode: (&*mode).into(),
+            loop_start,
+            loop_end,
+            pos: 0.0,
+            playing: false,
+            gated: false,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: SamplerFactory = SamplerFactory;
@@ -0,0 +1,138 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+
+// Semitone offsets within an octave, sorted and in [0, 12).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    pub offsets: Vec<f32>,
+}
+
+impl Scale {
+    pub fn named(name: &str) -> Option<Scale> {
+        let offsets: &[f32] = match name {
+            "chromatic" => &[0., 1., 2., 3., 4., 5., 6., 7., 8., 9., 10., 11.],
+            "major" | "ionian" => &[0., 2., 4., 5., 7., 9., 11.],
+            "minor" | "aeolian" => &[0., 2., 3., 5., 7., 8., 10.],
+            "dorian" => &[0., 2., 3., 5., 7., 9., 10.],
+            "phrygian" => &[0., 1., 3., 5., 7., 8., 10.],
+            "lydian" => &[0., 2., 4., 6., 7., 9., 11.],
+            "mixolydian" => &[0., 2., 4., 5., 7., 9., 10.],
+            "locrian" => &[0., 1., 3., 5., 6., 8., 10.],
+            "harmonic" => &[0., 2., 3., 5., 7., 8., 11.],
+            "melodic" => &[0., 2., 3., 5., 7., 9., 11.],
+            "pentatonic" => &[0., 2., 4., 7., 9.],
+            "minorpentatonic" => &[0., 3., 5., 7., 10.],
+            "blues" => &[0., 3., 5., 6., 7., 10.],
+            "wholetone" => &[0., 2., 4., 6., 8., 10.],
+            _ => return None,
+        };
+        Some(Scale {
+            offsets: offsets.to_vec(),
+        })
+    }
+
+    // Offsets separated by spaces or commas, e.g. '0 2 4 7 9'.
+    pub fn from_offsets(spec: &str) -> Scale {
+        let mut offsets: Vec<f32> = spec
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|s| s.parse::<f32>().ok())
+            .filter(|o| o.is_finite())
+            .map(|o| o.rem_euclid(12.0))
+            .collect();
+        offsets.sort_by(f32::total_cmp);
+        offsets.dedup();
+        Scale { offsets }
+    }
+
+    // Nearest scale degree to the MIDI note `note`, with the scale starting at `root`.
+    pub fn snap(&self, note: f32, root: f32) -> f32 {
+        let rel = note - root;
+        let octave = (rel / 12.0).floor();
+        let within = rel - 12.0 * octave;
+        let mut best = self.offsets[0];
+        for &o in self
+            .offsets
+            .iter()
+            .chain(Some(self.offsets[0] + 12.0).iter())
+        {
+            if (o - within).abs() < (best - within).abs() {
+                best = o;
+            }
+        }
+        root + 12.0 * octave + best
+    }
+}
+
+impl<'a> From<&'a str> for Scale {
+    fn from(s: &'a str) -> Scale {
+        match Scale::named(s) {
+            Some(scale) => scale,
+            None => {
+                let scale = Scale::from_offsets(s);
+                if scale.offsets.is_empty() {
+                    Scale::named("chromatic").unwrap()
+                } else {
+                    scale
+                }
+            }
+        }
+    }
+}
+
+// Snaps a pitch in MIDI note numbers to the nearest note of `scale` rooted at `root` (a MIDI
+// note; only its pitch class matters). Feed frequencies through ftom/mtof.
+#[derive(Debug)]
+pub struct Quantize {
+    pub input: GenBox,
+    pub root: GenBox,
+    pub scale: Scale,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Quantize {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let input = self.input.eval(params);
+        let root = self.root.eval(params);
+
+        if input.rate == Rate::Control && root.rate == Rate::Control {
+            self.buf.set(self.scale.snap(input.first(), root.first()));
+            return &self.buf;
+        }
+
+        self.buf.rate = Rate::Sample;
+        for i in 0..self.buf.len() {
+            self.buf[i] = self.scale.snap(input.value_at(i), root.value_at(i));
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct QuantizeFactory;
+
+impl GeneratorFactory for QuantizeFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let scale = params
+            .get_param("scale", 1, &mut ParamValue::String("chromatic".to_string()))
+            .as_string()?;
+        Ok(Box::new(Quantize {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            root: params
+                .remove_param("root", 2)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            scale: (&*scale).into(),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: QuantizeFactory = QuantizeFactory;
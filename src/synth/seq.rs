@@ -0,0 +1,193 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+
+// A pulse train at `rate` Hz, high for the first `width` of each period (starting high).
+#[derive(Debug)]
+pub struct Clock {
+    pub rate: GenBox,
+    pub width: GenBox,
+    pub phase: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Clock {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let rate = self.rate.eval(params);
+        let width = self.width.eval(params);
+        for i in 0..self.buf.len() {
+            self.buf[i] = if self.phase < width.value_at(i) {
+                1.0
+            } else {
+                0.0
+            };
+            self.phase = (self.phase + rate.value_at(i) / params.env.sample_rate).rem_euclid(1.0);
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct ClockFactory;
+
+impl GeneratorFactory for ClockFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Clock {
+            rate: params.remove_param("rate", 0)?.into_gen()?,
+            width: params
+                .remove_param("width", 1)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            phase: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryClock: ClockFactory = ClockFactory;
+
+// Counts rising edges of `trig` modulo `n` (unbounded if `n` is 0). The first edge outputs 0, so
+// the output can index steps directly; a rising edge of `reset` goes back to before the first
+// edge.
+#[derive(Debug)]
+pub struct Counter {
+    pub trig: GenBox,
+    pub reset: Option<GenBox>,
+    pub n: usize,
+    pub count: Option<usize>,
+    pub gated: bool,
+    pub reset_gated: bool,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Counter {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let trig = self.trig.eval(params);
+        let reset = self.reset.as_mut().map(|r| r.eval(params));
+        for i in 0..self.buf.len() {
+            if let Some(reset) = reset {
+                let gated = reset.value_at(i) >= 0.5;
+                if gated && !self.reset_gated {
+                    self.count = None;
+                }
+                self.reset_gated = gated;
+            }
+            let gated = trig.value_at(i) >= 0.5;
+            if gated && !self.gated {
+                let next = self.count.map_or(0, |c| c + 1);
+                self.count = Some(if self.n > 0 { next % self.n } else { next });
+            }
+            self.gated = gated;
+            self.buf[i] = self.count.unwrap_or(0) as Sample;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct CounterFactory;
+
+impl GeneratorFactory for CounterFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let reset = match params.remove_param("reset", 2) {
+            Ok(pv) => Some(pv.into_gen()?),
+            Err(_) => None,
+        };
+        Ok(Box::new(Counter {
+            trig: params.remove_param("trig", 0)?.into_gen()?,
+            reset,
+            n: params
+                .get_param("n", 1, &mut ParamValue::Integer(0))
+                .as_isize()?
+                .max(0) as usize,
+            count: None,
+            gated: false,
+            reset_gated: false,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryCounter: CounterFactory = CounterFactory;
+
+// Outputs values[index], with the index floored and wrapped around the list.
+#[derive(Debug)]
+pub struct Steps {
+    pub index: GenBox,
+    pub values: Vec<Sample>,
+    pub buf: SampleBuffer,
+}
+
+fn step_value(values: &[Sample], index: f32) -> Sample {
+    values[(index.floor() as isize).rem_euclid(values.len() as isize) as usize]
+}
+
+impl Generator for Steps {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let index = self.index.eval(params);
+
+        if index.rate == Rate::Control {
+            self.buf.set(step_value(&self.values, index.first()));
+            return &self.buf;
+        }
+
+        self.buf.rate = Rate::Sample;
+        for i in 0..self.buf.len() {
+            self.buf[i] = step_value(&self.values, index.value_at(i));
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct StepsFactory;
+
+impl GeneratorFactory for StepsFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mut values: Vec<Sample> = Vec::new();
+        while let Ok(val) = params
+            .get_req_param("_", 1 + values.len())
+            .and_then(|pv| pv.as_f32())
+        {
+            values.push(val);
+        }
+
+        if values.is_empty() {
+            return Err(GenFactoryError::MissingRequiredParam(
+                "values".to_string(),
+                1,
+            ));
+        }
+
+        Ok(Box::new(Steps {
+            index: params.remove_param("index", 0)?.into_gen()?,
+            values,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactorySteps: StepsFactory = StepsFactory;
@@ -0,0 +1,148 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeOp {
+    Saturate,
+    Clip,
+    Fold,
+    Crush,
+}
+
+impl ShapeOp {
+    // Name and default of the second parameter.
+    pub fn amount_param(&self) -> (&'static str, f32) {
+        match *self {
+            ShapeOp::Saturate | ShapeOp::Clip | ShapeOp::Fold => ("drive", 1.0),
+            ShapeOp::Crush => ("bits", 8.0),
+        }
+    }
+
+    pub fn apply(&self, x: Sample, amount: f32) -> Sample {
+        match *self {
+            ShapeOp::Saturate => (x * amount).tanh(),
+            ShapeOp::Clip => (x * amount).clamp(-1.0, 1.0),
+            ShapeOp::Fold => {
+                let t = (x * amount + 1.0).rem_euclid(4.0);
+                if t < 2.0 {
+                    t - 1.0
+                } else {
+                    3.0 - t
+                }
+            }
+            ShapeOp::Crush => {
+                let levels = 2f32.powf(amount.max(1.0) - 1.0);
+                (x * levels).round() / levels
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Shaper {
+    pub input: GenBox,
+    pub amount: GenBox,
+    pub op: ShapeOp,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Shaper {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let input = self.input.eval(params);
+        let amount = self.amount.eval(params);
+
+        if input.rate == Rate::Control && amount.rate == Rate::Control {
+            self.buf.set(self.op.apply(input.first(), amount.first()));
+            return &self.buf;
+        }
+
+        self.buf.rate = Rate::Sample;
+        for i in 0..self.buf.len() {
+            self.buf[i] = self.op.apply(input.value_at(i), amount.value_at(i));
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct ShaperFactory(pub ShapeOp);
+
+impl GeneratorFactory for ShaperFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let (name, default) = self.0.amount_param();
+        Ok(Box::new(Shaper {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            amount: params
+                .remove_param(name, 1)
+                .unwrap_or(ParamValue::Float(default))
+                .into_gen()?,
+            op: self.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactorySaturate: ShaperFactory = ShaperFactory(ShapeOp::Saturate);
+pub static FactoryClip: ShaperFactory = ShaperFactory(ShapeOp::Clip);
+pub static FactoryFold: ShaperFactory = ShaperFactory(ShapeOp::Fold);
+pub static FactoryCrush: ShaperFactory = ShaperFactory(ShapeOp::Crush);
+
+// Sample-and-hold at `rate` Hz, for sample rate reduction.
+#[derive(Debug)]
+pub struct Downsample {
+    pub input: GenBox,
+    pub rate: GenBox,
+    pub phase: f32,
+    pub held: Sample,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Downsample {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let rate = self.rate.eval(params);
+        for i in 0..self.buf.len() {
+            self.phase += rate.value_at(i) / params.env.sample_rate;
+            if self.phase >= 1.0 {
+                self.phase %= 1.0;
+                self.held = input.value_at(i);
+            }
+            self.buf[i] = self.held;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct DownsampleFactory;
+
+impl GeneratorFactory for DownsampleFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Downsample {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            rate: params.remove_param("rate", 1)?.into_gen()?,
+            // Start due, so the first sample is captured immediately.
+            phase: 1.0,
+            held: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryDownsample: DownsampleFactory = DownsampleFactory;
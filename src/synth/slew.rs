@@ -0,0 +1,129 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, Sample, SampleBuffer,
+};
+
+// Follows `input`, moving at most `rise` units per second upward and `fall` units per second
+// downward (`fall` defaults to `rise`). The first sample is taken as-is rather than slewed from 0.
+#[derive(Debug)]
+pub struct Slew {
+    pub input: GenBox,
+    pub rise: GenBox,
+    pub fall: Option<GenBox>,
+    pub cur: Option<Sample>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Slew {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let rise = self.rise.eval(params);
+        let fall = self.fall.as_mut().map(|f| f.eval(params)).unwrap_or(rise);
+        for i in 0..self.buf.len() {
+            let target = input.value_at(i);
+            let cur = match self.cur {
+                None => target,
+                Some(cur) => {
+                    let up = rise.value_at(i).abs() / params.env.sample_rate;
+                    let down = fall.value_at(i).abs() / params.env.sample_rate;
+                    cur + (target - cur).max(-down).min(up)
+                }
+            };
+            self.cur = Some(cur);
+            self.buf[i] = cur;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct SlewFactory;
+
+impl GeneratorFactory for SlewFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let fall = match params.remove_param("fall", 2) {
+            Ok(pv) => Some(pv.into_gen()?),
+            Err(_) => None,
+        };
+        Ok(Box::new(Slew {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            rise: params.remove_param("rise", 1)?.into_gen()?,
+            fall,
+            cur: None,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactorySlew: SlewFactory = SlewFactory;
+
+// One-pole smoothing with time constant `time` seconds (about 63% of the way to a new value in
+// that time); a time of 0 passes the input through. With `pitch` set the smoothing happens on
+// log2 of the input, so a frequency glides by equal intervals per unit time.
+#[derive(Debug)]
+pub struct Lag {
+    pub input: GenBox,
+    pub time: GenBox,
+    pub pitch: bool,
+    pub cur: Option<Sample>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Lag {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let time = self.time.eval(params);
+        for i in 0..self.buf.len() {
+            let target = if self.pitch {
+                input.value_at(i).max(1.0e-3).log2()
+            } else {
+                input.value_at(i)
+            };
+            let samples = time.value_at(i) * params.env.sample_rate;
+            let cur = match self.cur {
+                Some(cur) if samples > 1.0 => target + (cur - target) * (-1.0 / samples).exp(),
+                _ => target,
+            };
+            self.cur = Some(cur);
+            self.buf[i] = if self.pitch { cur.exp2() } else { cur };
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct LagFactory(pub bool);
+
+impl GeneratorFactory for LagFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        Ok(Box::new(Lag {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            time: params
+                .remove_param("time", 1)
+                .unwrap_or(ParamValue::Float(0.05))
+                .into_gen()?,
+            pitch: self.0,
+            cur: None,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static FactoryLag: LagFactory = LagFactory(false);
+pub static FactoryGlide: LagFactory = LagFactory(true);
@@ -6,6 +6,7 @@ use super::{
 #[derive(Debug)]
 pub struct Square {
     pub freq: GenBox,
+    pub width: GenBox,
     pub phase: f32,
     pub buf: SampleBuffer,
 }
@@ -15,8 +16,9 @@ impl Generator for Square {
         self.buf.rate = Rate::Sample;
 
         let pvel = self.freq.eval(params).first() / params.env.sample_rate;
+        let width = self.width.eval(params);
         for i in 0..self.buf.len() {
-            self.buf[i] = if ((self.phase + pvel * (i as f32)) % 1.0) < 0.5 {
+            self.buf[i] = if ((self.phase + pvel * (i as f32)) % 1.0) < width.value_at(i) {
                 -1.0
             } else {
                 1.0
@@ -40,6 +42,10 @@ impl GeneratorFactory for SquareFactory {
     fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
         Ok(Box::new(Square {
             freq: params.remove_param("freq", 0)?.into_gen()?,
+            width: params
+                .remove_param("width", 2)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
             phase: params
                 .get_param("phase", 1, &mut ParamValue::Float(0.0))
                 .as_f32()?,
@@ -0,0 +1,117 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvfMode {
+    LowPass,
+    BandPass,
+    HighPass,
+    Notch,
+    Peak,
+}
+
+impl SvfMode {
+    pub fn to_param_string(&self) -> &'static str {
+        match *self {
+            SvfMode::LowPass => "lowpass",
+            SvfMode::BandPass => "bandpass",
+            SvfMode::HighPass => "highpass",
+            SvfMode::Notch => "notch",
+            SvfMode::Peak => "peak",
+        }
+    }
+}
+
+impl<'a> From<&'a str> for SvfMode {
+    fn from(s: &'a str) -> SvfMode {
+        match s {
+            "bandpass" | "bp" => SvfMode::BandPass,
+            "highpass" | "hp" => SvfMode::HighPass,
+            "notch" => SvfMode::Notch,
+            "peak" => SvfMode::Peak,
+            _ => SvfMode::LowPass,
+        }
+    }
+}
+
+// Trapezoidal (zero-delay feedback) state-variable filter; the coefficients are cheap enough to
+// recompute every sample, which is what keeps audio-rate cutoff sweeps free of zipper noise.
+#[derive(Debug)]
+pub struct Svf {
+    pub input: GenBox,
+    pub cutoff: GenBox,
+    pub res: GenBox,
+    pub mode: SvfMode,
+    pub ic1eq: f32,
+    pub ic2eq: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Svf {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let input = self.input.eval(params);
+        let cutoff = self.cutoff.eval(params);
+        let res = self.res.eval(params);
+        let nyquist = 0.49 * params.env.sample_rate;
+        for i in 0..self.buf.len() {
+            let g = (PI * cutoff.value_at(i).max(1.0).min(nyquist) / params.env.sample_rate).tan();
+            let k = 2.0 - 2.0 * res.value_at(i).clamp(0.0, 0.995);
+            let a1 = 1.0 / (1.0 + g * (g + k));
+            let a2 = g * a1;
+            let a3 = g * a2;
+
+            let v0 = input.value_at(i);
+            let v3 = v0 - self.ic2eq;
+            let v1 = a1 * self.ic1eq + a2 * v3;
+            let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+            self.ic1eq = 2.0 * v1 - self.ic1eq;
+            self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+            let high = v0 - k * v1 - v2;
+            self.buf[i] = match self.mode {
+                SvfMode::LowPass => v2,
+                SvfMode::BandPass => v1,
+                SvfMode::HighPass => high,
+                SvfMode::Notch => v2 + high,
+                SvfMode::Peak => v2 - high,
+            };
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct SvfFactory;
+
+impl GeneratorFactory for SvfFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mode = params
+            .get_param("mode", 1, &mut ParamValue::String("lowpass".to_string()))
+            .as_string()?;
+        Ok(Box::new(Svf {
+            input: params.remove_param("input", 0)?.into_gen()?,
+            cutoff: params.remove_param("cutoff", 2)?.into_gen()?,
+            res: params
+                .remove_param("res", 3)
+                .unwrap_or(ParamValue::Float(0.0))
+                .into_gen()?,
+            mode: (&*mode).into(),
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: SvfFactory = SvfFactory;
@@ -0,0 +1,74 @@
+use super::blosc::{residual_width, OscShape};
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, Rate, SampleBuffer,
+};
+
+// An oscillator at `freq` whose phase is reset each time a hidden master oscillator at `master`
+// completes a cycle. The waveform's own edges are band-limited; the resets are not.
+#[derive(Debug)]
+pub struct HardSync {
+    pub freq: GenBox,
+    pub master: GenBox,
+    pub width: GenBox,
+    pub shape: OscShape,
+    pub phase: f32,
+    pub master_phase: f32,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for HardSync {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        self.buf.rate = Rate::Sample;
+
+        let freq = self.freq.eval(params);
+        let master = self.master.eval(params);
+        let width = self.width.eval(params);
+        for i in 0..self.buf.len() {
+            let pvel = freq.value_at(i) / params.env.sample_rate;
+            let mvel = master.value_at(i) / params.env.sample_rate;
+            self.buf[i] = self
+                .shape
+                .sample(self.phase, width.value_at(i), residual_width(pvel));
+            self.phase = (self.phase + pvel).rem_euclid(1.0);
+            self.master_phase += mvel;
+            if self.master_phase >= 1.0 || self.master_phase < 0.0 {
+                self.master_phase = self.master_phase.rem_euclid(1.0);
+                // Carry over however far past the reset the master got within this sample.
+                self.phase = (pvel * self.master_phase / mvel.abs().max(1.0e-9)).rem_euclid(1.0);
+            }
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct HardSyncFactory;
+
+impl GeneratorFactory for HardSyncFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let shape = params
+            .get_param("shape", 2, &mut ParamValue::String("saw".to_string()))
+            .as_string()?;
+        Ok(Box::new(HardSync {
+            freq: params.remove_param("freq", 0)?.into_gen()?,
+            master: params.remove_param("master", 1)?.into_gen()?,
+            width: params
+                .remove_param("width", 3)
+                .unwrap_or(ParamValue::Float(0.5))
+                .into_gen()?,
+            shape: (&*shape).into(),
+            phase: 0.0,
+            master_phase: 0.0,
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+}
+
+pub static Factory: HardSyncFactory = HardSyncFactory;
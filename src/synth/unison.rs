@@ -0,0 +1,95 @@
+use super::{
+    mem, FactoryParameters, GenBox, GenFactoryError, Generator, GeneratorFactory, ParamValue,
+    Parameters, SampleBuffer,
+};
+use std::collections::HashMap;
+
+// Runs `voices` independent copies of `gen` and sums them, scaled by 1/sqrt(voices) to keep
+// roughly the loudness of one. Each copy is evaluated with `var` (default "detune") set to its
+// pitch ratio, spread evenly across `spread` semitones, so `gen` detunes itself by multiplying
+// its frequency by it, as in `unison(saw(440 * detune), 7, 0.3)`. The copies start in phase.
+// Feedback names are shared by all the copies: an `fbwrite` in `gen` gives every voice a writer on
+// the same slot, so the last voice to write wins and each voice's `fbread` sees the others'
+// output rather than its own. Feedback loops belong outside the unison.
+#[derive(Debug)]
+pub struct Unison {
+    pub voices: Vec<GenBox>,
+    pub spread: GenBox,
+    pub var: String,
+    pub vars: HashMap<String, f32>,
+    pub buf: SampleBuffer,
+}
+
+impl Generator for Unison {
+    fn eval<'a>(&'a mut self, params: &Parameters) -> &'a SampleBuffer {
+        let spread = self.spread.eval(params).first();
+        let count = self.voices.len();
+        let gain = 1.0 / (count as f32).sqrt();
+        self.vars.clone_from(&params.vars);
+        let mut vparams = Parameters {
+            env: params.env.clone(),
+            vars: mem::take(&mut self.vars),
+        };
+        for (k, voice) in self.voices.iter_mut().enumerate() {
+            let offset = if count > 1 {
+                (k as f32) / ((count - 1) as f32) - 0.5
+            } else {
+                0.0
+            };
+            vparams
+                .vars
+                .insert(self.var.clone(), (offset * spread / 12.0).exp2());
+            let out = voice.eval(&vparams);
+            if k == 0 {
+                self.buf.update_from(out);
+            } else {
+                self.buf.sum_into(out);
+            }
+        }
+        self.vars = vparams.vars;
+        for s in self.buf.iter_mut() {
+            *s *= gain;
+        }
+
+        &self.buf
+    }
+    fn buffer(&self) -> &SampleBuffer {
+        &self.buf
+    }
+    fn set_buffer(&mut self, buf: SampleBuffer) -> SampleBuffer {
+        mem::replace(&mut self.buf, buf)
+    }
+}
+
+pub struct UnisonFactory;
+
+impl GeneratorFactory for UnisonFactory {
+    fn new(&self, params: &mut FactoryParameters) -> Result<GenBox, GenFactoryError> {
+        let mut voices = vec![params.remove_param("gen", 0)?.into_gen()?];
+        for copy in params.copies.iter_mut() {
+            voices.push(copy.remove_param("gen", 0)?.into_gen()?);
+        }
+        Ok(Box::new(Unison {
+            voices,
+            spread: params
+                .remove_param("spread", 2)
+                .unwrap_or(ParamValue::Float(0.3))
+                .into_gen()?,
+            var: params
+                .get_param("var", 3, &mut ParamValue::String("detune".to_string()))
+                .as_string()?,
+            vars: HashMap::new(),
+            buf: SampleBuffer::new(params.env.default_buffer_size),
+        }))
+    }
+
+    fn copies(&self, params: &mut FactoryParameters) -> usize {
+        params
+            .get_param("voices", 1, &mut ParamValue::Integer(7))
+            .as_isize()
+            .unwrap_or(1)
+            .max(1) as usize
+    }
+}
+
+pub static Factory: UnisonFactory = UnisonFactory;